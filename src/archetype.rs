@@ -0,0 +1,150 @@
+// archetype.rs
+// Tipos de enemigo: cada uno con su comportamiento (EnemyConfig), sprite,
+// sonido, jumpscare y glyph en el maze.txt del nivel.
+
+use crate::enemy::EnemyConfig;
+use crate::doors::DoorSkill;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Archetype {
+    /// Lento y constante (el de siempre)
+    Stalker,
+    /// Muy rápido persiguiendo, pero se cansa y tiene que parar
+    Sprinter,
+    /// Solo se mueve cuando no lo estás mirando
+    Angel,
+    /// Desaparece y reaparece en callejones sin salida
+    Teleporter,
+    /// No ve: caza de oído
+    Blind,
+}
+
+/// Recursos y glyph de un arquetipo.
+/// Las rutas `None` usan el recurso del tema del nivel ('e', 'j', loop del tema).
+pub struct ArchetypeDef {
+    pub glyph: char,                   // en el maze y clave de textura del sprite
+    pub sprite: Option<&'static str>,  // hoja direccional (8 filas x 4 frames)
+    pub jumpscare: char,               // clave de textura del overlay
+    pub jumpscare_img: Option<&'static str>,
+    pub loop_key: &'static str,        // música en bucle al acercarse
+    pub loop_sound: Option<&'static str>,
+    pub sfx_jumpscare: &'static str,   // clave de SFX del susto
+    pub sfx_jumpscare_path: Option<&'static str>,
+}
+
+pub const ALL_ARCHETYPES: [Archetype; 5] = [
+    Archetype::Stalker,
+    Archetype::Sprinter,
+    Archetype::Angel,
+    Archetype::Teleporter,
+    Archetype::Blind,
+];
+
+impl Archetype {
+    pub fn from_glyph(c: char) -> Option<Archetype> {
+        ALL_ARCHETYPES.into_iter().find(|a| a.def().glyph == c)
+    }
+
+    pub fn def(self) -> &'static ArchetypeDef {
+        match self {
+            Archetype::Stalker => &ArchetypeDef {
+                glyph: 'e',
+                sprite: None,
+                jumpscare: 'j',
+                jumpscare_img: None,
+                loop_key: "enemy_loop",
+                loop_sound: None,
+                sfx_jumpscare: "jumpscare",
+                sfx_jumpscare_path: None,
+            },
+            Archetype::Sprinter => &ArchetypeDef {
+                glyph: 'v',
+                sprite: Some("assets/enemies/sprinter_dirs.png"),
+                jumpscare: 'V',
+                jumpscare_img: Some("assets/enemies/sprinter_jumpscare.png"),
+                loop_key: "enemy_loop_sprinter",
+                loop_sound: Some("assets/audio/enemy_loop_sprinter.wav"),
+                sfx_jumpscare: "jumpscare_sprinter",
+                sfx_jumpscare_path: Some("assets/audio/sfx_jumpscare_sprinter.wav"),
+            },
+            Archetype::Angel => &ArchetypeDef {
+                glyph: 'w',
+                sprite: Some("assets/enemies/angel_dirs.png"),
+                jumpscare: 'W',
+                jumpscare_img: Some("assets/enemies/angel_jumpscare.png"),
+                loop_key: "enemy_loop_angel",
+                loop_sound: Some("assets/audio/enemy_loop_angel.wav"),
+                sfx_jumpscare: "jumpscare_angel",
+                sfx_jumpscare_path: Some("assets/audio/sfx_jumpscare_angel.wav"),
+            },
+            Archetype::Teleporter => &ArchetypeDef {
+                glyph: 't',
+                sprite: Some("assets/enemies/teleporter_dirs.png"),
+                jumpscare: 'T',
+                jumpscare_img: Some("assets/enemies/teleporter_jumpscare.png"),
+                loop_key: "enemy_loop_teleporter",
+                loop_sound: Some("assets/audio/enemy_loop_teleporter.wav"),
+                sfx_jumpscare: "jumpscare_teleporter",
+                sfx_jumpscare_path: Some("assets/audio/sfx_jumpscare_teleporter.wav"),
+            },
+            Archetype::Blind => &ArchetypeDef {
+                glyph: 'u',
+                sprite: Some("assets/enemies/blind_dirs.png"),
+                jumpscare: 'U',
+                jumpscare_img: Some("assets/enemies/blind_jumpscare.png"),
+                loop_key: "enemy_loop_blind",
+                loop_sound: Some("assets/audio/enemy_loop_blind.wav"),
+                sfx_jumpscare: "jumpscare_blind",
+                sfx_jumpscare_path: Some("assets/audio/sfx_jumpscare_blind.wav"),
+            },
+        }
+    }
+
+    /// Comportamiento del arquetipo (parte de `EnemyConfig::default()`).
+    pub fn config(self) -> EnemyConfig {
+        let base = EnemyConfig::default();
+        match self {
+            Archetype::Stalker => EnemyConfig {
+                speed_patrol: 35.0,
+                speed_investigate: 45.0,
+                speed_chase: 60.0,
+                speed_search: 40.0,
+                search_time: 12.0, // no se rinde fácilmente
+                doors: DoorSkill::Open { delay: 1.5 },
+                ..base
+            },
+            Archetype::Sprinter => EnemyConfig {
+                speed_chase: 135.0,
+                stamina: 3.5,
+                rest_time: 3.0,
+                tired_speed: 20.0,
+                doors: DoorSkill::Break { time: 4.0 },
+                ..base
+            },
+            Archetype::Angel => EnemyConfig {
+                speed_patrol: 70.0,
+                speed_investigate: 90.0,
+                speed_chase: 120.0,
+                speed_search: 80.0,
+                freeze_when_seen: true,
+                doors: DoorSkill::Open { delay: 1.0 },
+                ..base
+            },
+            Archetype::Teleporter => EnemyConfig {
+                speed_chase: 70.0,
+                teleport_every: 14.0, // no necesita puertas: aparece al otro lado
+                ..base
+            },
+            Archetype::Blind => EnemyConfig {
+                speed_investigate: 75.0,
+                speed_chase: 90.0,
+                sight_cells: 1.2,
+                fov: 0.0,     // solo lo nota al tocarlo (touch_cells)
+                notice_time: 0.2,
+                hearing: 2.0, // oye el doble de lejos
+                doors: DoorSkill::Break { time: 6.0 },
+                ..base
+            },
+        }
+    }
+}
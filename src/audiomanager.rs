@@ -5,6 +5,7 @@ use raylib::prelude::Vector2;
 
 use crate::gamemanager::GameState;
 use crate::level::LevelTheme;
+use crate::archetype::ALL_ARCHETYPES;
 
 /// Claves para identificar pistas
 const K_GAME: &str = "game";
@@ -84,7 +85,7 @@ impl AudioConfig {
             K_JUMP => self.vol_music_jumpscare,
             K_GO   => self.vol_music_gameover,
             K_WIN  => self.vol_music_win,
-            K_ENEM => self.vol_enemy_loop,
+            k if k.starts_with(K_ENEM) => self.vol_enemy_loop, // y los de cada arquetipo
             _ => 1.0,
         }
     }
@@ -94,7 +95,7 @@ impl AudioConfig {
         match key {
             "door_open" => self.vol_sfx_door_open,
             "key_pick"  => self.vol_sfx_key_pick,
-            "jumpscare" => self.vol_sfx_jumpscare,
+            k if k.starts_with("jumpscare") => self.vol_sfx_jumpscare,
             _ => 1.0,
         }
     }
@@ -125,7 +126,8 @@ pub struct AudioManager<'a> {
     target_key:  Option<&'static str>,
     fade_t: f32, // 0..1
 
-    // Enemy loop controlado aparte
+    // Enemy loop controlado aparte (el del arquetipo más cercano)
+    enemy_key: &'static str,
     enemy_gain: f32,     // 0..1 (calculado por distancia)
     enemy_max_dist: f32, // px
 
@@ -156,6 +158,24 @@ impl<'a> AudioManager<'a> {
         sfx.insert("door_open", ra.new_sound("assets/audio/sfx_door_open.wav").expect("sfx_door_open.wav"));
         sfx.insert("key_pick",  ra.new_sound("assets/audio/sfx_key_pick.wav").expect("sfx_key_pick.wav"));
         sfx.insert("jumpscare", ra.new_sound("assets/audio/sfx_jumpscare.wav").expect("sfx_jumpscare.wav"));
+        sfx.insert("teleport",  ra.new_sound("assets/audio/sfx_teleport.wav").expect("sfx_teleport.wav"));
+        sfx.insert("door_bang", ra.new_sound("assets/audio/sfx_door_bang.wav").expect("sfx_door_bang.wav"));
+        sfx.insert("door_break", ra.new_sound("assets/audio/sfx_door_break.wav").expect("sfx_door_break.wav"));
+        sfx.insert("hunt",      ra.new_sound("assets/audio/sfx_hunt.wav").expect("sfx_hunt.wav"));
+        sfx.insert("flare",     ra.new_sound("assets/audio/sfx_flare.wav").expect("sfx_flare.wav"));
+        sfx.insert("lure",      ra.new_sound("assets/audio/sfx_lure.wav").expect("sfx_lure.wav"));
+        sfx.insert("ward",      ra.new_sound("assets/audio/sfx_ward.wav").expect("sfx_ward.wav"));
+
+        // Loop y susto propios de cada arquetipo (los que no usan los del tema)
+        for a in ALL_ARCHETYPES {
+            let def = a.def();
+            if let Some(path) = def.loop_sound {
+                musics.insert(def.loop_key, ra.new_music(path).expect(path));
+            }
+            if let Some(path) = def.sfx_jumpscare_path {
+                sfx.insert(def.sfx_jumpscare, ra.new_sound(path).expect(path));
+            }
+        }
 
         let mut this = Self {
             ra,
@@ -164,6 +184,7 @@ impl<'a> AudioManager<'a> {
             current_key: None,
             target_key:  None,
             fade_t: 1.0,
+            enemy_key: K_ENEM,
             enemy_gain: 0.0,
             enemy_max_dist: cfg.enemy_max_dist,
             cfg,
@@ -243,13 +264,13 @@ impl<'a> AudioManager<'a> {
         }
 
         if self.cfg.enemy_loop_allowed_in(self.state) {
-            if let Some(loop_m) = self.musics.get(K_ENEM) {
-                let v = self.cfg.master_music * self.cfg.music_base(K_ENEM) * self.enemy_gain;
+            if let Some(loop_m) = self.musics.get(self.enemy_key) {
+                let v = self.cfg.master_music * self.cfg.music_base(self.enemy_key) * self.enemy_gain;
                 loop_m.set_volume(v.clamp(0.0, 1.0));
             }
         } else {
             self.enemy_gain = 0.0;
-            self.stop_music(K_ENEM);
+            self.stop_music(self.enemy_key);
         }
     }
 
@@ -258,12 +279,19 @@ impl<'a> AudioManager<'a> {
         self.state = st;
         if !self.cfg.enemy_loop_allowed_in(st) {
             self.enemy_gain = 0.0;
-            self.stop_music(K_ENEM);
+            self.stop_music(self.enemy_key);
         }
     }
 
+    /// Cercanía del enemigo más próximo (0 = fuera de oído, 1 = encima), la
+    /// misma que decide el volumen del loop (que es su cuadrado).
+    pub fn enemy_proximity(&self) -> f32 {
+        self.enemy_gain.sqrt()
+    }
+
     /// Ajusta volumen/pan del loop del enemigo según distancia y lado.
-    pub fn update_enemy_proximity<I: IntoIterator<Item = Vector2>>(
+    /// Cada enemigo trae la clave de su loop; suena el del más cercano.
+    pub fn update_enemy_proximity<I: IntoIterator<Item = (Vector2, &'static str)>>(
         &mut self,
         player_pos: Vector2,
         player_dir: f32,
@@ -279,15 +307,22 @@ impl<'a> AudioManager<'a> {
 
         let mut best_d2 = f32::INFINITY;
         let mut best_vec = Vector2::new(0.0, 0.0);
-        for epos in enemy_positions {
+        let mut best_key = self.enemy_key;
+        for (epos, key) in enemy_positions {
             let dx = epos.x - player_pos.x;
             let dy = epos.y - player_pos.y;
             let d2 = dx*dx + dy*dy;
-            if d2 < best_d2 { best_d2 = d2; best_vec = Vector2::new(dx, dy); }
+            if d2 < best_d2 { best_d2 = d2; best_vec = Vector2::new(dx, dy); best_key = key; }
         }
 
         if !best_d2.is_finite() { self.enemy_gain = 0.0; return; }
 
+        // Cambió el enemigo más cercano a otro arquetipo: cambia el loop
+        if best_key != self.enemy_key && self.musics.contains_key(best_key) {
+            self.stop_music(self.enemy_key);
+            self.enemy_key = best_key;
+        }
+
         let d = best_d2.sqrt();
         let raw = (1.0 - (d / self.enemy_max_dist)).clamp(0.0, 1.0);
         self.enemy_gain = raw * raw;
@@ -300,10 +335,10 @@ impl<'a> AudioManager<'a> {
         let cross = fx * ty - fy * tx; // [-1,1]
         let pan01 = (0.5 - 0.5 * cross).clamp(0.0, 1.0);
 
-        if let Some(loop_m) = self.musics.get(K_ENEM) {
+        if let Some(loop_m) = self.musics.get(self.enemy_key) {
             loop_m.set_pan(pan01);
             if self.enemy_gain > 0.0 && !loop_m.is_stream_playing() {
-                self.start_music_from_zero(K_ENEM);
+                self.start_music_from_zero(self.enemy_key);
             }
         }
     }
@@ -345,7 +380,7 @@ impl<'a> AudioManager<'a> {
 
     fn apply_mix_volumes(&mut self) {
         for (k, m) in self.musics.iter() {
-            if *k != K_ENEM { m.set_volume(0.0); }
+            if !k.starts_with(K_ENEM) { m.set_volume(0.0); }
         }
 
         let mm = self.cfg.master_music.clamp(0.0, 1.0);
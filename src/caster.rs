@@ -1,7 +1,9 @@
 use raylib::color::Color;
+use raylib::prelude::Vector2;
 use crate::framebuffer::Framebuffer;
 use crate::player::Player;
 use crate::maze::Maze;
+use crate::registry::is_key_door;
 
 /// Qué golpeó el rayo y a qué distancia (en píxeles) + parte fraccional para UV.
 pub struct Intersect {
@@ -9,18 +11,36 @@ pub struct Intersect {
     pub impact:   char,
     /// fracción 0..1 a lo largo de la cara golpeada (para calcular tx)
     pub hit_frac: f32,
+    /// celda (x,y) del muro golpeado
+    pub cell: (i32, i32),
+    /// normal de la cara golpeada, apunta hacia el origen del rayo ((0,0) si no hubo impacto)
+    pub normal: (i32, i32),
+    /// 0 = cara vertical (cruce en x), 1 = cara horizontal (cruce en y)
+    pub side: u8,
+    /// punto de impacto en el mundo (px)
+    pub point: Vector2,
 }
 
 // Define qué cuenta como pared sólida para el DDA (los sprites 'e' NO son pared)
 #[inline]
-fn is_wall(c: char) -> bool {
-    // Añadimos puertas (Y,B,R,G) como sólidos para el DDA
-    matches!(c, '+' | '-' | '|' | '#' | 'g' | 'Y' | 'B' | 'R' | 'G' | '@' | '!')
+pub fn is_wall(c: char) -> bool {
+    // Añadimos puertas (las de color del registro y la salida G) como sólidos para el DDA
+    matches!(c, '+' | '-' | '|' | '#' | 'g' | 'G' | '@' | '!') || is_key_door(c)
 }
 
+/// Bloques "de rejilla": frenan al jugador/enemigos pero el rayo sigue detrás
+/// ('=' reja, ':' alambrada, '/' ventana rota, '~' barrera del amuleto).
+#[inline]
+pub fn is_see_through(c: char) -> bool {
+    matches!(c, '=' | ':' | '/' | '~')
+}
+
+/// Máximo de capas transparentes que se dibujan por columna.
+pub const MAX_SEE_THROUGH: usize = 4;
+
 #[inline]
 pub fn is_passable(c: char) -> bool {
-    !is_wall(c)
+    !is_wall(c) && !is_see_through(c)
 }
 
 /// Lanza un rayo desde el jugador en ángulo `a`.
@@ -33,13 +53,95 @@ pub fn cast_ray(
     a: f32,
     draw_line: bool,
 ) -> Intersect {
-    let dir_x = a.cos();
-    let dir_y = a.sin();
+    let inter = cast_ray_dda(maze, player.pos, block_size, a);
+
+    // Dibujo de rayo en 2D (para depurar/vista 2D), sampleado cada ~4px para no matar FPS
+    if draw_line {
+        let dir_x = a.cos();
+        let dir_y = a.sin();
+        let step = 4.0_f32;
+        let mut t = 0.0_f32;
+        while t <= inter.distance {
+            let x = (player.pos.x + dir_x * t) as i32;
+            let y = (player.pos.y + dir_y * t) as i32;
+            framebuffer.set_current_color(Color::WHITESMOKE);
+            framebuffer.set_pixel(x, y);
+            t += step;
+        }
+    }
+
+    inter
+}
+
+/// DDA puro desde `origin` (px) en ángulo `a`: no toca el framebuffer,
+/// así que se puede llamar desde varios hilos a la vez.
+/// Sin impacto devuelve `impact = ' '` y distancia 20000 (como siempre).
+pub fn cast_ray_dda(
+    maze: &Maze,
+    origin: Vector2,
+    block_size: usize,
+    a: f32,
+) -> Intersect {
+    let dir = Vector2::new(a.cos(), a.sin());
+    dda(maze, origin, dir, f32::INFINITY, block_size, is_wall, None)
+        .unwrap_or_else(|miss| miss)
+}
+
+/// Como `cast_ray_dda`, pero además guarda en `layers` (de cerca a lejos) las
+/// caras de bloques transparentes que el rayo atraviesa antes del muro opaco.
+pub fn cast_ray_layers(
+    maze: &Maze,
+    origin: Vector2,
+    block_size: usize,
+    a: f32,
+    layers: &mut Vec<Intersect>,
+) -> Intersect {
+    layers.clear();
+    let dir = Vector2::new(a.cos(), a.sin());
+    dda(maze, origin, dir, f32::INFINITY, block_size, is_wall, Some(layers))
+        .unwrap_or_else(|miss| miss)
+}
+
+/// Rayo genérico para gameplay: primer bloque que cumple `filter` a menos de
+/// `max_dist` px desde `origin` en dirección `dir` (no hace falta normalizarla).
+pub fn raycast(
+    maze: &Maze,
+    origin: Vector2,
+    dir: Vector2,
+    max_dist: f32,
+    block_size: usize,
+    filter: impl Fn(char) -> bool,
+) -> Option<Intersect> {
+    let len = (dir.x * dir.x + dir.y * dir.y).sqrt();
+    if len < 1e-6 { return None; }
+    dda(maze, origin, Vector2::new(dir.x / len, dir.y / len), max_dist, block_size, filter, None).ok()
+}
+
+/// ¿Se ve `b` desde `a`? (ningún muro/puerta cerrada en medio)
+pub fn line_of_sight(maze: &Maze, a: Vector2, b: Vector2, block_size: usize) -> bool {
+    let d = Vector2::new(b.x - a.x, b.y - a.y);
+    let len = (d.x * d.x + d.y * d.y).sqrt();
+    if len < 1e-3 { return true; }
+    raycast(maze, a, d, len, block_size, is_wall).is_none()
+}
+
+/// DDA común. `dir` normalizada. Ok = impacto; Err = el rayo salió del mapa
+/// o pasó de `max_dist` (con la celda donde se quedó y distancia 20000).
+fn dda(
+    maze: &Maze,
+    origin: Vector2,
+    dir: Vector2,
+    max_dist: f32,
+    block_size: usize,
+    filter: impl Fn(char) -> bool,
+    mut layers: Option<&mut Vec<Intersect>>,
+) -> Result<Intersect, Intersect> {
+    let (dir_x, dir_y) = (dir.x, dir.y);
     let bs = block_size as f32;
 
-    // mapa (celda) donde empieza el jugador
-    let mut map_x = (player.pos.x / bs).floor() as i32;
-    let mut map_y = (player.pos.y / bs).floor() as i32;
+    // mapa (celda) donde empieza el rayo
+    let mut map_x = (origin.x / bs).floor() as i32;
+    let mut map_y = (origin.y / bs).floor() as i32;
 
     // distancias para avanzar de borde a borde de celda
     let delta_dist_x = if dir_x.abs() < 1e-6 { f32::INFINITY } else { (bs / dir_x.abs()) };
@@ -47,26 +149,29 @@ pub fn cast_ray(
 
     // pasos y distancias iniciales hasta el primer borde
     let (step_x, mut side_dist_x) = if dir_x < 0.0 {
-        let dist = ((player.pos.x - (map_x as f32 * bs)) / dir_x.abs()).abs();
+        let dist = ((origin.x - (map_x as f32 * bs)) / dir_x.abs()).abs();
         (-1, dist)
     } else {
-        let dist = ((((map_x + 1) as f32 * bs) - player.pos.x) / dir_x.abs()).abs();
+        let dist = ((((map_x + 1) as f32 * bs) - origin.x) / dir_x.abs()).abs();
         (1, dist)
     };
 
     let (step_y, mut side_dist_y) = if dir_y < 0.0 {
-        let dist = ((player.pos.y - (map_y as f32 * bs)) / dir_y.abs()).abs();
+        let dist = ((origin.y - (map_y as f32 * bs)) / dir_y.abs()).abs();
         (-1, dist)
     } else {
-        let dist = ((((map_y + 1) as f32 * bs) - player.pos.y) / dir_y.abs()).abs();
+        let dist = ((((map_y + 1) as f32 * bs) - origin.y) / dir_y.abs()).abs();
         (1, dist)
     };
 
     let mut hit = false;
-    let mut side = 0; // 0 = cruce vertical (pared "NS"), 1 = horizontal (pared "EW")
+    let mut side: u8 = 0; // 0 = cruce vertical (pared "NS"), 1 = horizontal (pared "EW")
 
     // DDA: saltar de borde a borde de celda
     while !hit {
+        // distancia al borde que vamos a cruzar: más allá del máximo, no hay impacto
+        if side_dist_x.min(side_dist_y) > max_dist { break; }
+
         if side_dist_x < side_dist_y {
             side_dist_x += delta_dist_x;
             map_x += step_x;
@@ -80,45 +185,64 @@ pub fn cast_ray(
         if map_y < 0 || map_y as usize >= maze.len() { break; }
         if map_x < 0 || map_x as usize >= maze[map_y as usize].len() { break; }
 
-        // Solo choca si es pared sólida
-        if is_wall(maze[map_y as usize][map_x as usize]) {
+        // Solo choca si el filtro acepta el bloque
+        let c = maze[map_y as usize][map_x as usize];
+        if filter(c) {
             hit = true;
+        } else if is_see_through(c) && let Some(v) = layers.as_deref_mut() && v.len() < MAX_SEE_THROUGH {
+            let raw = if side == 0 { side_dist_x - delta_dist_x } else { side_dist_y - delta_dist_y };
+            v.push(face_hit(origin, dir, bs, raw.max(1e-4), c, Crossing { side, cell: (map_x, map_y), step: (step_x, step_y) }));
         }
     }
 
+    if !hit {
+        // Sin impacto: distancia "infinita" y sin cara
+        let mut miss = face_hit(origin, dir, bs, 20_000.0, ' ', Crossing { side, cell: (map_x, map_y), step: (step_x, step_y) });
+        miss.normal = (0, 0);
+        return Err(miss);
+    }
+
     // Distancia al primer muro (corrección a la última suma)
-    let dist = if hit {
-        let raw = if side == 0 { (side_dist_x - delta_dist_x) } else { (side_dist_y - delta_dist_y) };
-        raw.max(1e-4)
-    } else {
-        20_000.0
-    };
+    let raw = if side == 0 { (side_dist_x - delta_dist_x) } else { (side_dist_y - delta_dist_y) };
+    let c = maze[map_y as usize][map_x as usize];
+    Ok(face_hit(origin, dir, bs, raw.max(1e-4), c, Crossing { side, cell: (map_x, map_y), step: (step_x, step_y) }))
+}
+
+/// Cara que cruza el DDA: celda, eje del cruce (`Intersect::side`) y sentido del paso.
+#[derive(Clone, Copy)]
+struct Crossing {
+    side: u8,
+    cell: (i32, i32),
+    step: (i32, i32),
+}
 
-    // Coordenada del punto de impacto para hit_frac
-    let xf = player.pos.x + dist * dir_x;
-    let yf = player.pos.y + dist * dir_y;
+/// Arma el `Intersect` de la cara cruzada a distancia `dist` del origen.
+#[inline]
+fn face_hit(
+    origin: Vector2,
+    dir: Vector2,
+    bs: f32,
+    dist: f32,
+    impact: char,
+    at: Crossing,
+) -> Intersect {
+    let Crossing { side, cell, step } = at;
+    // Punto de impacto en el mundo y fracción a lo largo de la cara (UV)
+    let xf = origin.x + dist * dir.x;
+    let yf = origin.y + dist * dir.y;
     let local_x = (xf % bs + bs) % bs;
     let local_y = (yf % bs + bs) % bs;
     let hit_frac = if side == 0 { (local_y / bs).fract() } else { (local_x / bs).fract() };
 
-    let impact = if hit {
-        maze[map_y as usize][map_x as usize]
-    } else {
-        ' '
-    };
+    let normal = if side == 0 { (-step.0, 0) } else { (0, -step.1) };
 
-    // Dibujo de rayo en 2D (para depurar/vista 2D), sampleado cada ~4px para no matar FPS
-    if draw_line {
-        let step = 4.0_f32;
-        let mut t = 0.0_f32;
-        while t <= dist {
-            let x = (player.pos.x + dir_x * t) as i32;
-            let y = (player.pos.y + dir_y * t) as i32;
-            framebuffer.set_current_color(Color::WHITESMOKE);
-            framebuffer.set_pixel(x, y);
-            t += step;
-        }
+    Intersect {
+        distance: dist,
+        impact,
+        hit_frac,
+        cell,
+        normal,
+        side,
+        point: Vector2::new(xf, yf),
     }
-
-    Intersect { distance: dist, impact, hit_frac }
 }
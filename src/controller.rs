@@ -1,6 +1,10 @@
 use raylib::prelude::*;
 use crate::player::Player;
 
+/// Cómo se movió el jugador este frame (sigilo, ruido).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Movement { Still, Walk, Sprint }
+
 #[inline]
 fn wrap_angle(mut a: f32) -> f32 {
     use std::f32::consts::TAU;
@@ -15,7 +19,7 @@ pub fn process_events(
     dt: f32,
     screen_w: i32,
     screen_h: i32,
-) {
+) -> Movement {
     const MOVE_SPEED: f32 = 80.0;    // px/s
     const SPRINT_MULT: f32 = 1.5;    // 50% más rápido
     const MOUSE_SENS: f32 = 0.0010;  // rad/pixel
@@ -45,6 +49,7 @@ pub fn process_events(
         (window.is_key_down(KeyboardKey::KEY_D) || window.is_key_down(KeyboardKey::KEY_RIGHT)) as i32
       - (window.is_key_down(KeyboardKey::KEY_A) || window.is_key_down(KeyboardKey::KEY_LEFT)) as i32;
 
+    let mut movement = Movement::Still;
     if forward != 0 || strafe != 0 {
         let ca = player.a.cos();
         let sa = player.a.sin();
@@ -61,12 +66,15 @@ pub fn process_events(
 
             // sprint con SHIFT
             let mut speed = MOVE_SPEED;
+            movement = Movement::Walk;
             if window.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || window.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT) {
                 speed *= SPRINT_MULT;
+                movement = Movement::Sprint;
             }
 
             player.pos.x += mx * speed * dt;
             player.pos.y += my * speed * dt;
         }
     }
+    movement
 }
\ No newline at end of file
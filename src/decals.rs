@@ -0,0 +1,138 @@
+// decals.rs
+// Calcomanías pegadas a la cara de un muro: arañazos, sangre, notas...
+// Ayudan a reconocer pasillos (y a contar algo de historia).
+
+use std::collections::HashMap;
+use crate::caster::is_passable;
+use crate::maze::Maze;
+
+/// Texturas de decal (claves en TextureManager)
+pub const DECAL_SCRATCH: char = 'x';
+pub const DECAL_BLOOD: char   = 'z';
+pub const DECAL_NOTE: char    = 'q';
+
+/// Cara de un muro, según su normal (hacia dónde mira).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Face { North, South, East, West }
+
+impl Face {
+    pub fn from_normal(n: (i32, i32)) -> Option<Face> {
+        match n {
+            (0, -1) => Some(Face::North),
+            (0, 1)  => Some(Face::South),
+            (1, 0)  => Some(Face::East),
+            (-1, 0) => Some(Face::West),
+            _ => None,
+        }
+    }
+
+    pub fn normal(self) -> (i32, i32) {
+        match self {
+            Face::North => (0, -1),
+            Face::South => (0, 1),
+            Face::East  => (1, 0),
+            Face::West  => (-1, 0),
+        }
+    }
+
+    /// En estas caras `hit_frac` crece de derecha a izquierda vista de frente:
+    /// hay que invertir u para que el decal no salga en espejo.
+    #[inline]
+    pub fn flips_u(self) -> bool {
+        matches!(self, Face::East | Face::North)
+    }
+}
+
+/// Rectángulo del decal sobre la cara, en coords 0..1 (u a lo ancho, v de arriba abajo).
+#[derive(Clone, Copy, Debug)]
+pub struct Decal {
+    pub tex: char,
+    pub u0: f32,
+    pub v0: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+#[derive(Default)]
+pub struct DecalSet {
+    by_face: HashMap<(i32, i32, Face), Vec<Decal>>,
+}
+
+impl DecalSet {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn add(&mut self, cell: (i32, i32), face: Face, decal: Decal) {
+        self.by_face.entry((cell.0, cell.1, face)).or_default().push(decal);
+    }
+
+    /// Decals de la cara de `cell` con normal `normal` (vacío si no hay).
+    #[inline]
+    pub fn on_face(&self, cell: (i32, i32), normal: (i32, i32)) -> (Option<Face>, &[Decal]) {
+        let Some(face) = Face::from_normal(normal) else { return (None, &[]); };
+        match self.by_face.get(&(cell.0, cell.1, face)) {
+            Some(v) => (Some(face), v.as_slice()),
+            None    => (Some(face), &[]),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool { self.by_face.is_empty() }
+}
+
+pub struct DecalConfig {
+    pub chance: f32,     // probabilidad de decal por cara de muro visible
+    pub note_share: f32, // fracción de esos decals que son notas
+}
+
+impl Default for DecalConfig {
+    fn default() -> Self {
+        Self { chance: 0.05, note_share: 0.2 }
+    }
+}
+
+// Hash entero barato: el mismo mapa da siempre los mismos decals
+#[inline]
+fn hash3(x: i32, y: i32, k: u32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6b343) ^ (y as u32).wrapping_mul(0xd8163841) ^ k.wrapping_mul(0xcb1ab31f);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1e995);
+    h ^ (h >> 15)
+}
+
+#[inline]
+fn unit(h: u32) -> f32 { (h & 0xffff) as f32 / 65536.0 }
+
+/// Reparte decals por las caras de muro que dan a un pasillo.
+/// Las puertas no llevan decals (cambian al abrirse).
+pub fn scatter_decals(maze: &Maze, cfg: &DecalConfig) -> DecalSet {
+    let mut set = DecalSet::new();
+    for (j, row) in maze.iter().enumerate() {
+        for (i, &c) in row.iter().enumerate() {
+            if !matches!(c, '+' | '-' | '|' | '#' | '@' | '!') { continue; }
+
+            for (k, face) in [Face::North, Face::South, Face::East, Face::West].into_iter().enumerate() {
+                let (nx, ny) = face.normal();
+                let (ni, nj) = (i as i32 + nx, j as i32 + ny);
+                if nj < 0 || nj as usize >= maze.len() { continue; }
+                if ni < 0 || ni as usize >= maze[nj as usize].len() { continue; }
+                if !is_passable(maze[nj as usize][ni as usize]) { continue; }
+
+                let h = hash3(i as i32, j as i32, k as u32);
+                if unit(h) >= cfg.chance { continue; }
+
+                let r = unit(h >> 16);
+                let pick = unit(hash3(i as i32, j as i32, k as u32 + 7));
+                let decal = if pick < cfg.note_share {
+                    // Nota a la altura de los ojos
+                    Decal { tex: DECAL_NOTE, u0: 0.3 + r * 0.35, v0: 0.36, w: 0.22, h: 0.28 }
+                } else if pick < cfg.note_share + (1.0 - cfg.note_share) * 0.5 {
+                    Decal { tex: DECAL_SCRATCH, u0: 0.1 + r * 0.35, v0: 0.25, w: 0.5, h: 0.45 }
+                } else {
+                    // Mancha de sangre cerca del suelo
+                    Decal { tex: DECAL_BLOOD, u0: 0.05 + r * 0.4, v0: 0.5, w: 0.5, h: 0.45 }
+                };
+                set.add((i as i32, j as i32), face, decal);
+            }
+        }
+    }
+    set
+}
@@ -0,0 +1,151 @@
+// doors.rs
+// Puertas de color (las del registro) frente a los enemigos: unos las abren tras
+// forcejear un rato, otros las echan abajo a golpes y otros las rodean.
+// El daño se ve en la puerta (grietas como decals) y se oye.
+
+use std::collections::HashMap;
+use crate::decals::{Decal, DecalSet, Face, DECAL_SCRATCH};
+use crate::caster::is_passable;
+use crate::maze::Maze;
+use crate::registry::is_key_door;
+
+/// Puertas que un enemigo puede cruzar (la salida 'G' nunca).
+#[inline]
+pub fn is_door(c: char) -> bool {
+    is_key_door(c)
+}
+
+/// Coste extra de pathfinding por cruzar una puerta (prefieren rodear si no es mucho más largo).
+pub const DOOR_STEP_COST: u32 = 6;
+
+/// Qué sabe hacer un enemigo con una puerta cerrada.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DoorSkill {
+    /// No puede: la rodea (o espera)
+    None,
+    /// La abre tras `delay` segundos forcejeando
+    Open { delay: f32 },
+    /// La revienta en `time` segundos de golpes
+    Break { time: f32 },
+}
+
+impl DoorSkill {
+    #[inline]
+    pub fn passes_doors(self) -> bool { self != DoorSkill::None }
+}
+
+/// Qué pasó en la puerta este frame (para SFX, ruido y replanificar).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DoorEvent {
+    Opened,
+    Hit,    // golpe (cada `bang_every` seg)
+    Broken,
+}
+
+#[derive(Clone, Copy)]
+pub struct DoorConfig {
+    pub bang_every: f32, // seg entre golpes que se oyen
+    pub crack_stages: u8, // grietas que aparecen hasta romperse
+}
+
+impl Default for DoorConfig {
+    fn default() -> Self {
+        Self { bang_every: 0.6, crack_stages: 3 }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct DoorWork {
+    progress: f32, // 0..1 (apertura o daño)
+    bang: f32,     // acumulador de golpes
+    stage: u8,     // grietas ya pintadas
+}
+
+pub struct Doors {
+    pub cfg: DoorConfig,
+    work: HashMap<(i32,i32), DoorWork>,
+}
+
+impl Doors {
+    pub fn new(cfg: DoorConfig) -> Self {
+        Self { cfg, work: HashMap::new() }
+    }
+
+    pub fn reset(&mut self) {
+        self.work.clear();
+    }
+
+    /// La puerta dejó de existir (abierta por el jugador, por ejemplo).
+    pub fn forget(&mut self, cell: (i32,i32)) {
+        self.work.remove(&cell);
+    }
+
+    /// Un enemigo con `skill` trabaja `dt` segundos sobre la puerta de `cell`.
+    /// Al abrirse o romperse la celda pasa a ser pasillo.
+    pub fn work(
+        &mut self,
+        maze: &mut Maze,
+        decals: &mut DecalSet,
+        cell: (i32,i32),
+        skill: DoorSkill,
+        dt: f32,
+    ) -> Option<DoorEvent> {
+        let (ci, cj) = cell;
+        if cj < 0 || ci < 0 { return None; }
+        let c = *maze.get(cj as usize)?.get(ci as usize)?;
+        if !is_door(c) { return None; }
+
+        let cfg = self.cfg;
+        let w = self.work.entry(cell).or_default();
+        let event = match skill {
+            DoorSkill::None => return None,
+            DoorSkill::Open { delay } => {
+                w.progress += dt / delay.max(1e-3);
+                (w.progress >= 1.0).then_some(DoorEvent::Opened)
+            }
+            DoorSkill::Break { time } => {
+                w.progress += dt / time.max(1e-3);
+                w.bang += dt;
+
+                // Grietas según el daño
+                let stage = (w.progress * (cfg.crack_stages + 1) as f32) as u8;
+                while w.stage < stage.min(cfg.crack_stages) {
+                    w.stage += 1;
+                    crack(maze, decals, cell, w.stage);
+                }
+
+                if w.progress >= 1.0 {
+                    Some(DoorEvent::Broken)
+                } else if w.bang >= cfg.bang_every {
+                    w.bang -= cfg.bang_every;
+                    Some(DoorEvent::Hit)
+                } else { None }
+            }
+        };
+
+        if matches!(event, Some(DoorEvent::Opened | DoorEvent::Broken)) {
+            maze[cj as usize][ci as usize] = ' ';
+            self.work.remove(&cell);
+        }
+        event
+    }
+}
+
+/// Pinta una grieta más en las caras de la puerta que dan a pasillo.
+fn crack(maze: &Maze, decals: &mut DecalSet, (ci, cj): (i32,i32), stage: u8) {
+    for face in [Face::North, Face::South, Face::East, Face::West] {
+        let (nx, ny) = face.normal();
+        let (ni, nj) = (ci + nx, cj + ny);
+        if ni < 0 || nj < 0 { continue; }
+        let open = maze.get(nj as usize).and_then(|r| r.get(ni as usize)).is_some_and(|&c| is_passable(c));
+        if !open { continue; }
+        let k = stage as f32;
+        decals.add((ci, cj), face, Decal {
+            tex: DECAL_SCRATCH,
+            u0: 0.15 + 0.2 * (k - 1.0),
+            v0: 0.15 + 0.15 * (k - 1.0),
+            w: 0.5,
+            h: 0.5,
+        });
+    }
+}
@@ -1,29 +1,262 @@
 use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashSet};
+use std::collections::{BinaryHeap, VecDeque};
+use rand::Rng;
 use raylib::prelude::Vector2;
 use crate::maze::Maze;
-use crate::caster::is_passable;
+use crate::player::Player;
+use crate::archetype::Archetype;
+use crate::flowfield::Flows;
+use crate::doors::{is_door, DoorSkill, DOOR_STEP_COST};
+use crate::caster::{is_passable, line_of_sight, raycast};
+use crate::physics::resolve_player_collisions;
 use crate::utils_grid::{world_to_cell, cell_center};
 
+/// Qué está haciendo el enemigo.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EnemyState {
+    /// Ronda entre sus waypoints
+    Patrol,
+    /// Va a ver qué fue ese ruido
+    Investigate { cell: (i32,i32) },
+    /// Ve al jugador y va a por él
+    Chase,
+    /// Lo perdió de vista: rebusca alrededor de `origin` durante `left` segundos
+    Search { origin: (i32,i32), left: f32 },
+}
+
+/// Seg que un objetivo sin camino se da por imposible antes de reintentar A*.
+const UNREACHABLE_BACKOFF: f32 = 2.0;
+
+/// Comportamiento configurable (velocidades en px/seg, distancias en celdas).
+#[derive(Clone, Copy, Debug)]
+pub struct EnemyConfig {
+    pub speed_patrol: f32,
+    pub speed_investigate: f32,
+    pub speed_chase: f32,
+    pub speed_search: f32,
+    pub sight_cells: f32,    // alcance de la vista
+    pub fov: f32,            // apertura del cono de visión (rad)
+    pub touch_cells: f32,    // tan cerca lo nota aunque esté fuera del cono
+    pub notice_time: f32,    // seg para detectar del todo a un jugador muy visible y pegado
+    pub forget_time: f32,    // seg para olvidar una sospecha completa
+    pub suspicious: f32,     // con esta alerta (0..1) va a mirar
+    pub search_time: f32,    // segundos rebuscando antes de volver a patrullar
+    pub search_radius: i32,  // celdas alrededor del último sitio conocido
+    pub patrol_points: usize,
+    pub patrol_radius: usize, // distancia (BFS) máxima de los waypoints al spawn
+    pub hearing: f32,        // multiplica el alcance de los ruidos
+    pub stamina: f32,        // seg de persecución antes de cansarse (INFINITY = nunca)
+    pub rest_time: f32,      // seg recuperando el aliento
+    pub tired_speed: f32,    // velocidad mientras descansa
+    pub freeze_when_seen: bool, // se queda quieto si el jugador lo mira
+    pub teleport_every: f32, // seg entre teletransportes (0 = no teletransporta)
+    pub doors: DoorSkill,    // abre, rompe o rodea las puertas
+    pub radius: f32,         // radio del cuerpo (fracción de celda)
+    pub lookahead: usize,    // nodos del camino que intenta atajar en línea recta
+    pub separation: f32,     // distancia (celdas) a la que empieza a apartarse de otros
+    pub separation_weight: f32,
+    pub arrive_cells: f32,   // a esta distancia de un nodo ya pasa al siguiente
+}
+
+impl Default for EnemyConfig {
+    fn default() -> Self {
+        Self {
+            speed_patrol: 45.0,
+            speed_investigate: 60.0,
+            speed_chase: 80.0,
+            speed_search: 50.0,
+            sight_cells: 8.0,
+            fov: 100f32.to_radians(),
+            touch_cells: 0.8,
+            notice_time: 0.6,
+            forget_time: 4.0,
+            suspicious: 0.4,
+            search_time: 8.0,
+            search_radius: 3,
+            patrol_points: 4,
+            patrol_radius: 14,
+            hearing: 1.0,
+            stamina: f32::INFINITY,
+            rest_time: 0.0,
+            tired_speed: 0.0,
+            freeze_when_seen: false,
+            teleport_every: 0.0,
+            doors: DoorSkill::None,
+            radius: 0.22,
+            lookahead: 4,
+            separation: 0.9,
+            separation_weight: 1.2,
+            arrive_cells: 0.2,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Enemy {
+    pub kind: Archetype,
     pub pos: Vector2,          // posición en píxeles
-    pub speed: f32,            // px/seg
     pub path: Vec<(i32,i32)>,  // camino en celdas (desde el siguiente paso)
+    pub heading: f32,          // rad, hacia dónde mira/camina (cono de visión y sprite)
+    pub turn_rate: f32,        // rad/seg
+    pub state: EnemyState,
+    pub cfg: EnemyConfig,
+    pub awareness: f32,        // 0..1, 1 = lo ha detectado
+    pub waypoints: Vec<(i32,i32)>,
+    pub frozen: bool,          // este frame no se movió porque lo miraban
+    pub teleported: bool,      // este frame se teletransportó (para el SFX)
+    pub door_push: Option<(i32,i32)>, // puerta en la que está trabajando este frame
+    pub speed_mult: f32,       // escalada del nivel (ver GameManager::speed_mult)
+    pub hunting: bool,         // fase de caza: sabe dónde está el jugador aunque no lo vea
+    pub stunned: f32,          // seg que le quedan deslumbrado (bengala): ni ve ni se mueve
+    stamina: f32,
+    rest_left: f32,
+    teleport_left: f32,
+    wp_index: usize,
+    goal: Option<(i32,i32)>,   // celda a la que va el camino actual
     replan_accum: f32,
+    unreachable: Option<((i32,i32), f32)>, // último objetivo sin camino y seg que se ignora
 }
 
 impl Enemy {
-    pub fn from_cell(ci: i32, cj: i32, bs: usize) -> Self {
+    pub fn new(kind: Archetype, ci: i32, cj: i32, bs: usize) -> Self {
+        let cfg = kind.config();
         Self {
+            kind,
             pos: cell_center(ci, cj, bs),
-            speed: 70.0,
             path: Vec::new(),
+            heading: 0.0,
+            turn_rate: 6.0,
+            state: EnemyState::Patrol,
+            cfg,
+            awareness: 0.0,
+            waypoints: Vec::new(),
+            frozen: false,
+            teleported: false,
+            door_push: None,
+            speed_mult: 1.0,
+            hunting: false,
+            stunned: 0.0,
+            stamina: cfg.stamina,
+            rest_left: 0.0,
+            teleport_left: cfg.teleport_every,
+            wp_index: 0,
+            goal: None,
             replan_accum: 0.0,
+            unreachable: None,
+        }
+    }
+
+    /// Velocidad del estado actual (px/seg).
+    pub fn speed(&self) -> f32 {
+        if self.rest_left > 0.0 { return self.cfg.tired_speed * self.speed_mult; }
+        let base = match self.state {
+            EnemyState::Patrol             => self.cfg.speed_patrol,
+            EnemyState::Investigate { .. } => self.cfg.speed_investigate,
+            EnemyState::Chase              => self.cfg.speed_chase,
+            EnemyState::Search { .. }      => self.cfg.speed_search,
+        };
+        base * self.speed_mult
+    }
+
+    /// Oyó algo en `cell`: si no está persiguiendo, va a mirar.
+    /// Si la celda es sólida (una puerta) va a la vecina caminable más cercana.
+    pub fn hear(&mut self, maze: &Maze, cell: (i32,i32), block_size: usize) {
+        if self.state == EnemyState::Chase { return; }
+        let here = world_to_cell(self.pos.x, self.pos.y, block_size);
+        let passable = cell.0 >= 0 && cell.1 >= 0
+            && maze.get(cell.1 as usize).and_then(|r| r.get(cell.0 as usize)).is_some_and(|&c| is_passable(c));
+        let cell = if passable {
+            cell
+        } else {
+            match neighbors(cell.0, cell.1, maze)
+                .min_by_key(|&(x, y)| x.abs_diff(here.0) + y.abs_diff(here.1))
+            {
+                Some(n) => n,
+                None => return,
+            }
+        };
+        // Ya sabe que ahí no llega: no insiste hasta que pase el rato
+        if self.unreachable.is_some_and(|(c, _)| c == cell) { return; }
+        self.state = EnemyState::Investigate { cell };
+    }
+
+    /// Deslumbrado `secs` segundos: pierde la pista del jugador.
+    pub fn stun(&mut self, secs: f32) {
+        self.stunned = self.stunned.max(secs);
+        self.awareness = 0.0;
+        self.path.clear();
+    }
+
+    fn set_goal(&mut self, goal: Option<(i32,i32)>) {
+        // Objetivo que acaba de fallar: sigue "visitado" hasta que pase el rato
+        if goal.is_some() && self.unreachable.is_some_and(|(c, _)| Some(c) == goal) { return; }
+        if self.goal != goal {
+            self.goal = goal;
+            self.path.clear();
+            self.replan_accum = f32::INFINITY; // replanifica ya
         }
     }
 }
 
+/// Celdas caminables alcanzables desde `start` a menos de `max_steps` pasos (BFS).
+fn reachable_cells(maze: &Maze, start: (i32,i32), max_steps: usize) -> Vec<(i32,i32)> {
+    let grid = Grid::of(maze);
+    let mut seen = vec![false; grid.len()];
+    let mut out = Vec::new();
+    let mut q = VecDeque::new();
+    if let Some(k) = grid.index(start) { seen[k] = true; }
+    q.push_back((start, 0usize));
+    while let Some((c, d)) = q.pop_front() {
+        out.push(c);
+        if d >= max_steps { continue; }
+        for n in neighbors(c.0, c.1, maze) {
+            let k = grid.index(n).unwrap();
+            if !seen[k] { seen[k] = true; q.push_back((n, d + 1)); }
+        }
+    }
+    out
+}
+
+/// Callejones sin salida: celdas caminables con una sola vecina caminable.
+fn dead_ends(maze: &Maze) -> Vec<(i32,i32)> {
+    let mut v = Vec::new();
+    for (j, row) in maze.iter().enumerate() {
+        for (i, &c) in row.iter().enumerate() {
+            if is_passable(c) && neighbors(i as i32, j as i32, maze).count() == 1 {
+                v.push((i as i32, j as i32));
+            }
+        }
+    }
+    v
+}
+
+/// Elige al azar `count` waypoints de ronda cerca de `start`.
+fn pick_waypoints(maze: &Maze, start: (i32,i32), count: usize, radius: usize) -> Vec<(i32,i32)> {
+    let cells = reachable_cells(maze, start, radius);
+    let mut rng = rand::rng();
+    let mut v = vec![start];
+    for _ in 1..count.max(1) {
+        if cells.len() <= 1 { break; }
+        v.push(cells[rng.random_range(1..cells.len())]);
+    }
+    v
+}
+
+/// Dimensiones del maze para guardar datos por celda en arrays planos.
+#[derive(Clone, Copy)]
+struct Grid { w: i32, h: i32 }
+
+impl Grid {
+    fn of(maze: &Maze) -> Self {
+        Self { w: maze.iter().map(|r| r.len()).max().unwrap_or(0) as i32, h: maze.len() as i32 }
+    }
+    fn len(&self) -> usize { (self.w * self.h) as usize }
+    #[inline]
+    fn index(&self, (x, y): (i32,i32)) -> Option<usize> {
+        (x >= 0 && y >= 0 && x < self.w && y < self.h).then(|| (y * self.w + x) as usize)
+    }
+}
+
 // --- A* ---
 #[derive(Clone, Eq, PartialEq)]
 struct Node { f: i32, g: i32, x: i32, y: i32 }
@@ -38,6 +271,19 @@ fn manhattan(ax: i32, ay: i32, bx: i32, by: i32) -> i32 {
     (ax - bx).abs() + (ay - by).abs()
 }
 
+/// Vecinas a las que se puede dar un paso y su coste (las puertas cuestan más).
+fn steps(x: i32, y: i32, maze: &Maze, through_doors: bool) -> impl Iterator<Item=((i32,i32), i32)> + '_ {
+    const OFFS: [(i32,i32);4] = [(1,0),(-1,0),(0,1),(0,-1)];
+    OFFS.into_iter().filter_map(move |(dx,dy)| {
+        let nx = x+dx; let ny = y+dy;
+        if ny < 0 || nx < 0 { return None; }
+        let c = *maze.get(ny as usize)?.get(nx as usize)?;
+        if is_passable(c) { Some(((nx,ny), 1)) }
+        else if through_doors && is_door(c) { Some(((nx,ny), 1 + DOOR_STEP_COST as i32)) }
+        else { None }
+    })
+}
+
 fn neighbors(x: i32, y: i32, maze: &Maze) -> impl Iterator<Item=(i32,i32)> + '_ {
     const OFFS: [(i32,i32);4] = [(1,0),(-1,0),(0,1),(0,-1)];
     OFFS.into_iter().filter_map(move |(dx,dy)| {
@@ -50,36 +296,42 @@ fn neighbors(x: i32, y: i32, maze: &Maze) -> impl Iterator<Item=(i32,i32)> + '_
     })
 }
 
-pub fn astar(maze: &Maze, start: (i32,i32), goal: (i32,i32)) -> Option<Vec<(i32,i32)>> {
+/// `through_doors`: las puertas cuentan como paso (caro) en vez de muro.
+pub fn astar(maze: &Maze, start: (i32,i32), goal: (i32,i32), through_doors: bool) -> Option<Vec<(i32,i32)>> {
     if start == goal { return Some(vec![]); }
     let (gx,gy) = goal;
+    let grid = Grid::of(maze);
+    let (Some(si), Some(_)) = (grid.index(start), grid.index(goal)) else { return None; };
     let mut open = BinaryHeap::new();
-    let mut came: HashSet<(i32,i32)> = HashSet::new();
-    let mut parent = std::collections::HashMap::new();
-    let mut gscore = std::collections::HashMap::new();
+    let mut closed = vec![false; grid.len()];
+    let mut parent: Vec<u32> = vec![u32::MAX; grid.len()];
+    let mut gscore = vec![i32::MAX; grid.len()];
 
     let h0 = manhattan(start.0, start.1, gx, gy);
     open.push(Node { f: h0, g:0, x: start.0, y: start.1 });
-    gscore.insert(start, 0);
+    gscore[si] = 0;
 
     while let Some(Node { g, x, y, .. }) = open.pop() {
         if (x,y) == goal {
             let mut path = Vec::new();
-            let mut cur = (x,y);
-            while let Some(&p) = parent.get(&cur) {
-                path.push(cur);
-                cur = p;
+            let mut cur = grid.index((x,y)).unwrap();
+            while parent[cur] != u32::MAX {
+                path.push(((cur as i32) % grid.w, (cur as i32) / grid.w));
+                cur = parent[cur] as usize;
             }
             path.reverse();
             return Some(path);
         }
 
-        if !came.insert((x,y)) { continue; }
-        for (nx,ny) in neighbors(x,y, maze) {
-            let tentative = g + 1;
-            if tentative < *gscore.get(&(nx,ny)).unwrap_or(&i32::MAX) {
-                parent.insert((nx,ny), (x,y));
-                gscore.insert((nx,ny), tentative);
+        let k = grid.index((x,y)).unwrap();
+        if closed[k] { continue; }
+        closed[k] = true;
+        for ((nx,ny), cost) in steps(x, y, maze, through_doors) {
+            let tentative = g + cost;
+            let nk = grid.index((nx,ny)).unwrap();
+            if tentative < gscore[nk] {
+                parent[nk] = k as u32;
+                gscore[nk] = tentative;
                 let h = manhattan(nx, ny, gx, gy);
                 open.push(Node { f: tentative + h, g: tentative, x: nx, y: ny });
             }
@@ -88,41 +340,304 @@ pub fn astar(maze: &Maze, start: (i32,i32), goal: (i32,i32)) -> Option<Vec<(i32,
     None
 }
 
-pub fn update_enemy(
-    e: &mut Enemy,
-    maze: &Maze,
-    player_pos: Vector2,
-    block_size: usize,
-    dt: f32,
-) {
-    // Replanifica a ~10Hz
+/// Gira `heading` hacia `target` como mucho `max_step` rad.
+fn turn_towards(heading: &mut f32, target: f32, max_step: f32) {
+    let mut d = target - *heading;
+    while d > std::f32::consts::PI { d -= std::f32::consts::TAU; }
+    while d < -std::f32::consts::PI { d += std::f32::consts::TAU; }
+    *heading += d.clamp(-max_step, max_step);
+}
+
+/// Cercanía (0..1) del jugador si está dentro del cono y sin muros en medio;
+/// None si no lo puede ver. Muy de cerca lo nota aunque esté de espaldas.
+fn sight_of_player(e: &Enemy, maze: &Maze, player_pos: Vector2, block_size: usize) -> Option<f32> {
+    let bs = block_size as f32;
+    let to = player_pos - e.pos;
+    let d = to.length();
+    let range = e.cfg.sight_cells * bs;
+    if d > range { return None; }
+
+    if d > e.cfg.touch_cells * bs {
+        let mut off = to.y.atan2(to.x) - e.heading;
+        while off > std::f32::consts::PI { off -= std::f32::consts::TAU; }
+        while off < -std::f32::consts::PI { off += std::f32::consts::TAU; }
+        if off.abs() > e.cfg.fov * 0.5 { return None; }
+    }
+
+    if !line_of_sight(maze, e.pos, player_pos, block_size) { return None; }
+    Some(1.0 - d / range)
+}
+
+/// ¿Lo tiene el jugador en pantalla? (dentro de su FOV y sin muros en medio)
+fn watched_by(e: &Enemy, maze: &Maze, player: &Player, block_size: usize) -> bool {
+    let to = e.pos - player.pos;
+    let d = to.length().max(1e-3);
+    // Margen: medio sprite a esa distancia
+    let half_w = (0.5 * block_size as f32 / d).atan();
+    let mut off = to.y.atan2(to.x) - player.a;
+    while off > std::f32::consts::PI { off -= std::f32::consts::TAU; }
+    while off < -std::f32::consts::PI { off += std::f32::consts::TAU; }
+    off.abs() <= player.fov * 0.5 + half_w && line_of_sight(maze, player.pos, e.pos, block_size)
+}
+
+/// Salta a un callejón sin salida a media distancia del jugador.
+fn teleport(e: &mut Enemy, maze: &Maze, player_cell: (i32,i32), block_size: usize) {
+    let ends = dead_ends(maze);
+    let d2 = |c: &(i32,i32)| (c.0 - player_cell.0).pow(2) + (c.1 - player_cell.1).pow(2);
+    let near: Vec<(i32,i32)> = ends.iter().copied().filter(|c| (16..=144).contains(&d2(c))).collect();
+    let pool = if near.is_empty() {
+        ends.into_iter().filter(|c| d2(c) >= 16).collect()
+    } else { near };
+    if pool.is_empty() { return; }
+
+    let cell = pool[rand::rng().random_range(0..pool.len())];
+    e.pos = cell_center(cell.0, cell.1, block_size);
+    e.path.clear();
+    e.goal = None;
+    e.state = EnemyState::Search { origin: cell, left: e.cfg.search_time };
+    e.teleported = true;
+}
+
+/// ¿Cabe el cuerpo (radio `r` px) en línea recta de `a` a `b`? Tres rayos: centro y los dos bordes.
+fn clear_walk(maze: &Maze, a: Vector2, b: Vector2, r: f32, block_size: usize) -> bool {
+    let to = b - a;
+    let len = to.length();
+    if len < 1e-3 { return true; }
+    let dir = to / len;
+    let side = Vector2::new(-dir.y, dir.x) * r;
+    [Vector2::zero(), side, side * -1.0].iter().all(|&off| {
+        raycast(maze, a + off, dir, len, block_size, |c| !is_passable(c)).is_none()
+    })
+}
+
+/// Empuje para separarse de los enemigos cercanos de `crowd` (salvo él mismo, `me`).
+fn separation(e: &Enemy, crowd: &[Vector2], me: usize, block_size: usize) -> Vector2 {
+    let reach = e.cfg.separation * block_size as f32;
+    let mut push = Vector2::zero();
+    for (k, &p) in crowd.iter().enumerate() {
+        if k == me { continue; }
+        let away = e.pos - p;
+        let d = away.length();
+        if d >= reach { continue; }
+        let dir = if d > 1e-3 { away / d } else {
+            // Justo encima: se separan en direcciones distintas según el índice
+            let a = (me as f32 - k as f32) * 2.4;
+            Vector2::new(a.cos(), a.sin())
+        };
+        push += dir * (1.0 - d / reach);
+    }
+    push
+}
+
+/// Lo que comparten todos los enemigos en un frame (ver `update_enemy`).
+pub struct EnemyFrame<'a> {
+    pub maze: &'a Maze,
+    pub player: &'a Player,
+    /// Lo visible que está el jugador (0..1), ver `Stealth`
+    pub visibility: f32,
+    /// Campos de flujo hacia la celda del jugador (los usan los que persiguen)
+    pub flows: &'a Flows,
+    /// Posiciones de todos los enemigos (para separarse)
+    pub crowd: &'a [Vector2],
+    pub block_size: usize,
+    pub dt: f32,
+}
+
+/// `me`: índice de este enemigo en `frame.crowd`.
+pub fn update_enemy(e: &mut Enemy, me: usize, frame: &EnemyFrame) {
+    let EnemyFrame { maze, player, visibility, flows, crowd, block_size, dt } = *frame;
+    let player_pos = player.pos;
+    e.frozen = false;
+    e.teleported = false;
+    e.door_push = None;
+    let through_doors = e.cfg.doors.passes_doors();
+    let flow = flows.for_doors(through_doors);
+    let here = world_to_cell(e.pos.x, e.pos.y, block_size);
+    let pc = world_to_cell(player_pos.x, player_pos.y, block_size);
+
+    if let Some((cell, left)) = e.unreachable {
+        e.unreachable = (left > dt).then_some((cell, left - dt));
+    }
+
+    // Deslumbrado: quieto; al recuperarse rebusca donde estaba
+    if e.stunned > 0.0 {
+        e.stunned -= dt;
+        e.frozen = true;
+        if e.stunned <= 0.0 {
+            e.stunned = 0.0;
+            e.state = EnemyState::Search { origin: here, left: e.cfg.search_time };
+            e.set_goal(None);
+        }
+        return;
+    }
+
+    if e.waypoints.is_empty() {
+        e.waypoints = pick_waypoints(maze, here, e.cfg.patrol_points, e.cfg.patrol_radius);
+    }
+
+    // --- Detección: la alerta sube más rápido cuanto más cerca y visible ---
+    let sight = sight_of_player(e, maze, player_pos, block_size);
+    match sight {
+        Some(near) => {
+            let rate = visibility * (0.25 + 0.75 * near) / e.cfg.notice_time.max(1e-3);
+            e.awareness = (e.awareness + rate * dt).min(1.0);
+        }
+        None => {
+            e.awareness = (e.awareness - dt / e.cfg.forget_time.max(1e-3)).max(0.0);
+        }
+    }
+
+    // --- Transiciones ---
+    if e.hunting || (sight.is_some() && (e.awareness >= 1.0 || e.state == EnemyState::Chase)) {
+        // En la caza no hace falta verlo: van todos a por él
+        e.state = EnemyState::Chase;
+    } else if sight.is_some() && e.awareness >= e.cfg.suspicious {
+        // Algo ha visto: va a mirar
+        e.hear(maze, pc, block_size);
+    } else if e.state == EnemyState::Chase {
+        // Lo perdió: rebusca donde lo vio por última vez
+        let origin = e.goal.unwrap_or(here);
+        e.state = EnemyState::Search { origin, left: e.cfg.search_time };
+    }
+
+    // --- Cansancio (sprinter): corre mientras le quede aliento ---
+    if e.rest_left > 0.0 {
+        e.rest_left -= dt;
+        if e.rest_left <= 0.0 { e.stamina = e.cfg.stamina; }
+    } else if e.state == EnemyState::Chase {
+        e.stamina -= dt;
+        if e.stamina <= 0.0 { e.rest_left = e.cfg.rest_time; }
+    } else {
+        e.stamina = (e.stamina + dt).min(e.cfg.stamina);
+    }
+
+    // --- Teletransporte: solo si no lo persigue y nadie lo mira ---
+    if e.cfg.teleport_every > 0.0 && e.state != EnemyState::Chase {
+        e.teleport_left -= dt;
+        if e.teleport_left <= 0.0 && !watched_by(e, maze, player, block_size) {
+            e.teleport_left = e.cfg.teleport_every;
+            teleport(e, maze, pc, block_size);
+            return;
+        }
+    }
+
+    let arrived = e.path.is_empty() && e.goal.is_none_or(|g| g == here);
+    match e.state {
+        EnemyState::Patrol => {
+            if arrived && !e.waypoints.is_empty() {
+                e.wp_index = (e.wp_index + 1) % e.waypoints.len();
+            }
+            let wp = e.waypoints.get(e.wp_index).copied();
+            e.set_goal(wp);
+        }
+        EnemyState::Investigate { cell } => {
+            if arrived && e.goal == Some(cell) {
+                e.state = EnemyState::Search { origin: cell, left: e.cfg.search_time };
+            } else {
+                e.set_goal(Some(cell));
+            }
+        }
+        EnemyState::Chase => {
+            // Con el jugador a la vista (o en la caza) el objetivo sigue su celda
+            e.set_goal(Some(pc));
+        }
+        EnemyState::Search { origin, left } => {
+            let left = left - dt;
+            if left <= 0.0 {
+                e.state = EnemyState::Patrol;
+                e.set_goal(None);
+            } else {
+                e.state = EnemyState::Search { origin, left };
+                if arrived {
+                    // Siguiente rincón al azar cerca del último sitio conocido
+                    let r = e.cfg.search_radius.max(1) as usize;
+                    let near = reachable_cells(maze, origin, r);
+                    let pick = near[rand::rng().random_range(0..near.len())];
+                    e.set_goal(Some(pick));
+                }
+            }
+        }
+    }
+
+    // Replanifica a ~10Hz (o ya, si cambió el objetivo)
     e.replan_accum += dt;
-    if e.replan_accum >= 0.1 {
+    if e.state == EnemyState::Chase && flow.target() == Some(pc) {
+        // Persiguiendo: baja por el campo de flujo compartido, sin A* propio
+        if e.path.is_empty() || e.replan_accum >= 0.1 {
+            e.replan_accum = 0.0;
+            e.path = flow.path_from(here, 8);
+        }
+    } else if e.replan_accum >= 0.1 {
         e.replan_accum = 0.0;
-        let sc = world_to_cell(e.pos.x, e.pos.y, block_size);
-        let pc = world_to_cell(player_pos.x, player_pos.y, block_size);
-        if e.path.is_empty() || e.path.last().copied() != Some(pc) {
-            if let Some(mut path) = astar(maze, sc, pc) {
-                if !path.is_empty() && path[0] == sc { path.remove(0); }
-                e.path = path;
-            } else {
-                e.path.clear();
+        if let Some(goal) = e.goal
+            && (e.path.is_empty() || e.path.last().copied() != Some(goal))
+        {
+            match astar(maze, here, goal, through_doors) {
+                Some(mut path) => {
+                    if !path.is_empty() && path[0] == here { path.remove(0); }
+                    e.path = path;
+                }
+                None => {
+                    // Inalcanzable: lo damos por visitado y no vuelve a intentarlo
+                    // en un rato (si no, A* recorre todo el mapa cada frame)
+                    e.path.clear();
+                    e.goal = Some(here);
+                    e.unreachable = Some((goal, UNREACHABLE_BACKOFF));
+                    if matches!(e.state, EnemyState::Investigate { .. }) {
+                        e.state = EnemyState::Search { origin: here, left: e.cfg.search_time };
+                    }
+                }
             }
         }
     }
 
+    // El ángel no se mueve mientras lo miras
+    if e.cfg.freeze_when_seen && watched_by(e, maze, player, block_size) {
+        e.frozen = true;
+        return;
+    }
+
+    let bs = block_size as f32;
+    let radius = e.cfg.radius * bs;
+
+    // Atajo: salta al nodo más lejano (de los próximos) al que se llega en línea recta
+    let ahead = e.path.len().min(e.cfg.lookahead);
+    if let Some(k) = (1..ahead).rev().find(|&k| {
+        let (x, y) = e.path[k];
+        clear_walk(maze, e.pos, cell_center(x, y, block_size), radius, block_size)
+    }) {
+        e.path.drain(..k);
+    }
+
+    let mut desired = Vector2::zero();
     if let Some(&(nx,ny)) = e.path.first() {
         let target = cell_center(nx, ny, block_size);
-        let to = (target - e.pos);
-        let dist = to.length();
-        let step = e.speed * dt;
+        let to = target - e.pos;
 
-        if dist <= step {
-            e.pos = target;
+        // Puerta cerrada delante: se queda trabajando en ella (ver doors.rs)
+        if is_door(maze[ny as usize][nx as usize]) {
+            turn_towards(&mut e.heading, to.y.atan2(to.x), e.turn_rate * dt);
+            e.door_push = Some((nx, ny));
+            return;
+        }
+
+        let dist = to.length();
+        if dist <= (e.cfg.arrive_cells * bs).max(e.speed() * dt) {
             e.path.remove(0);
-        } else {
-            let dir = to / dist;
-            e.pos += dir * step;
         }
+        if dist > 1e-3 { desired = to / dist; }
     }
+
+    // Separación + avance, con la velocidad del estado como tope
+    let steer = desired + separation(e, crowd, me, block_size) * e.cfg.separation_weight;
+    let len = steer.length();
+    if len < 1e-3 { return; }
+    let vel = steer / len.max(1.0) * e.speed();
+
+    // Gira suave hacia donde realmente se mueve
+    turn_towards(&mut e.heading, vel.y.atan2(vel.x), e.turn_rate * dt);
+
+    // Avanza y se despega de los muros (misma colisión círculo-celda que el jugador)
+    e.pos += vel * dt;
+    resolve_player_collisions(&mut e.pos, radius, maze, block_size, 2);
 }
@@ -0,0 +1,128 @@
+// fear.rs
+// Efectos de miedo en pantalla según lo cerca que está el enemigo:
+// viñeta que late con el corazón, temblor, desaturación y cortes de imagen.
+
+/// Config de los efectos de miedo (todas las intensidades a miedo = 1).
+#[derive(Clone, Copy)]
+pub struct FearConfig {
+    pub rise: f32,        // velocidad a la que sube el miedo (1/s)
+    pub fall: f32,        // velocidad a la que baja (1/s)
+
+    pub bpm_calm: f32,    // latidos por minuto con miedo ~0
+    pub bpm_panic: f32,   // latidos por minuto con miedo 1
+    pub pulse_vignette: f32, // viñeta extra en cada latido
+    pub base_vignette: f32,  // viñeta extra constante
+
+    pub shake_px: f32,    // amplitud del temblor
+    pub desat: f32,       // desaturación máxima (0..1)
+
+    pub glitch_min: f32,  // miedo a partir del que puede haber cortes
+    pub glitch_rate: f32, // cortes por segundo a miedo 1
+    pub glitch_px: f32,   // desplazamiento máximo de la franja cortada
+}
+
+impl Default for FearConfig {
+    fn default() -> Self {
+        Self {
+            rise: 1.5,
+            fall: 0.6,
+
+            bpm_calm: 70.0,
+            bpm_panic: 150.0,
+            pulse_vignette: 0.35,
+            base_vignette: 0.2,
+
+            shake_px: 3.0,
+            desat: 0.6,
+
+            glitch_min: 0.55,
+            glitch_rate: 0.8,
+            glitch_px: 24.0,
+        }
+    }
+}
+
+/// Lo que tiene que aplicar el post-procesado este frame.
+#[derive(Clone, Copy, Default)]
+pub struct FearFrame {
+    pub vignette: f32,          // fuerza extra de viñeta
+    pub shake: (i32, i32),      // desplazamiento de la imagen (px)
+    pub desat: f32,             // 0..1
+    pub tear: Option<(f32, f32, i32)>, // franja (y0, y1 en 0..1 del viewport) y desplazamiento x
+}
+
+impl FearFrame {
+    pub fn is_idle(&self) -> bool {
+        self.vignette <= 0.0 && self.shake == (0, 0) && self.desat <= 0.0 && self.tear.is_none()
+    }
+}
+
+pub struct Fear {
+    pub cfg: FearConfig,
+    pub level: f32,  // 0..1 suavizado
+    beat_phase: f32, // 0..1 dentro del latido actual
+    tear_left: f32,  // segundos que le quedan al corte actual
+    tear: (f32, f32, i32),
+}
+
+impl Fear {
+    pub fn new(cfg: FearConfig) -> Self {
+        Self { cfg, level: 0.0, beat_phase: 0.0, tear_left: 0.0, tear: (0.0, 0.0, 0) }
+    }
+
+    pub fn reset(&mut self) {
+        self.level = 0.0;
+        self.beat_phase = 0.0;
+        self.tear_left = 0.0;
+    }
+
+    /// `proximity`: cercanía del enemigo (0..1), la de `AudioManager::enemy_proximity`.
+    pub fn update(&mut self, dt: f32, proximity: f32) {
+        let target = proximity.clamp(0.0, 1.0);
+        let rate = if target > self.level { self.cfg.rise } else { self.cfg.fall };
+        self.level += (target - self.level) * (rate * dt).min(1.0);
+
+        let bpm = self.cfg.bpm_calm + (self.cfg.bpm_panic - self.cfg.bpm_calm) * self.level;
+        self.beat_phase = (self.beat_phase + dt * bpm / 60.0).fract();
+
+        // Cortes de imagen esporádicos con miedo alto
+        if self.tear_left > 0.0 {
+            self.tear_left -= dt;
+        } else if self.level > self.cfg.glitch_min
+            && rand::random::<f32>() < self.cfg.glitch_rate * self.level * dt
+        {
+            let y0 = rand::random::<f32>() * 0.85;
+            let hgt = 0.03 + rand::random::<f32>() * 0.12;
+            let dir = if rand::random::<bool>() { 1.0 } else { -1.0 };
+            let off = (dir * self.cfg.glitch_px * (0.3 + 0.7 * rand::random::<f32>())) as i32;
+            self.tear = (y0, y0 + hgt, off);
+            self.tear_left = 0.05 + rand::random::<f32>() * 0.08;
+        }
+    }
+
+    /// Latido "lub-dub": dos golpes rápidos al principio de cada ciclo.
+    fn heartbeat(&self) -> f32 {
+        let p = self.beat_phase;
+        let lub = (-(p / 0.06).powi(2)).exp();
+        let dub = (-((p - 0.22) / 0.06).powi(2)).exp() * 0.6;
+        lub + dub
+    }
+
+    pub fn frame(&self, time_s: f32) -> FearFrame {
+        let f = self.level;
+        if f <= 0.01 { return FearFrame::default(); }
+
+        let amp = self.cfg.shake_px * f * f;
+        let shake = (
+            (amp * ((time_s * 37.0).sin() * 0.6 + (time_s * 23.0).sin() * 0.4)).round() as i32,
+            (amp * ((time_s * 29.0).cos() * 0.6 + (time_s * 41.0).sin() * 0.4)).round() as i32,
+        );
+
+        FearFrame {
+            vignette: f * (self.cfg.base_vignette + self.cfg.pulse_vignette * self.heartbeat()),
+            shake,
+            desat: self.cfg.desat * f,
+            tear: (self.tear_left > 0.0).then_some(self.tear),
+        }
+    }
+}
@@ -0,0 +1,122 @@
+// flashlight.rs
+// Linterna del jugador: cono de luz en la dirección de vista + batería.
+
+/// Glyph del mapa para las pilas (pickup que recarga la batería).
+pub const BATTERY_GLYPH: char = '%';
+
+#[derive(Clone, Copy)]
+pub struct Flashlight {
+    pub on: bool,
+    pub battery: f32,      // 0..1
+    pub drain_per_s: f32,  // batería consumida por segundo con la luz encendida
+    pub refill: f32,       // batería que recarga cada pila recogida
+    pub low_battery: f32,  // por debajo de este nivel la luz se debilita y parpadea
+
+    pub cone: f32,         // semi-ángulo del cono (radianes)
+    pub soft: f32,         // fracción del cono que hace de borde suave (0..1)
+    pub range_cells: f32,  // alcance del haz (en celdas)
+    pub ambient: f32,      // luz fuera del cono (casi negro)
+}
+
+impl Default for Flashlight {
+    fn default() -> Self {
+        Self {
+            on: true,
+            battery: 1.0,
+            drain_per_s: 1.0 / 120.0, // ~2 min de luz con la batería llena
+            refill: 0.35,
+            low_battery: 0.2,
+
+            cone: 0.32,
+            soft: 0.45,
+            range_cells: 6.0,
+            ambient: 0.08,
+        }
+    }
+}
+
+impl Flashlight {
+    /// Enciende/apaga. Sin batería no se puede encender.
+    pub fn toggle(&mut self) {
+        self.on = !self.on && self.battery > 0.0;
+    }
+
+    /// Llama una vez por frame (solo en Playing) para descargar la batería.
+    pub fn update(&mut self, dt: f32) {
+        if !self.on { return; }
+        self.battery = (self.battery - self.drain_per_s * dt).max(0.0);
+        if self.battery <= 0.0 { self.on = false; }
+    }
+
+    /// Recarga con una pila.
+    pub fn recharge(&mut self) {
+        self.battery = (self.battery + self.refill).min(1.0);
+    }
+
+    /// Intensidad del haz (0..1): se debilita y parpadea con batería baja.
+    pub fn intensity(&self, time_s: f32) -> f32 {
+        if !self.on || self.battery <= 0.0 { return 0.0; }
+        if self.battery >= self.low_battery { return 1.0; }
+
+        let k = self.battery / self.low_battery.max(1e-4);
+        let flicker = 0.85 + 0.15 * (time_s * 23.0).sin() * (time_s * 7.3).sin();
+        (0.35 + 0.65 * k) * flicker
+    }
+}
+
+/// Haz precalculado para un frame/viewport: el cono se proyecta a un
+/// círculo en pantalla (coords normalizadas por el semi-ancho).
+pub struct Beam {
+    cx: f32,
+    cy: f32,
+    inv_hw: f32,
+    inner2: f32,
+    outer2: f32,
+    range: f32,
+    intensity: f32,
+    ambient: f32,
+}
+
+impl Beam {
+    pub fn new(
+        light: &Flashlight,
+        fov: f32,
+        viewport_w: i32,
+        viewport_y0: i32,
+        viewport_h: i32,
+        block_size: usize,
+        time_s: f32,
+    ) -> Self {
+        let hw = viewport_w as f32 * 0.5;
+        let outer = light.cone.tan() / (fov * 0.5).tan();
+        let inner = outer * (1.0 - light.soft.clamp(0.0, 1.0));
+        Self {
+            cx: hw,
+            cy: viewport_y0 as f32 + viewport_h as f32 * 0.5,
+            inv_hw: 1.0 / hw.max(1.0),
+            inner2: inner * inner,
+            outer2: outer * outer,
+            range: (light.range_cells * block_size as f32).max(1.0),
+            intensity: light.intensity(time_s),
+            ambient: light.ambient,
+        }
+    }
+
+    /// Multiplicador de luz para el píxel (x,y) a distancia `dist` (px).
+    #[inline]
+    pub fn light_at(&self, x: i32, y: i32, dist: f32) -> f32 {
+        if self.intensity <= 0.0 { return self.ambient; }
+
+        let nx = (x as f32 - self.cx) * self.inv_hw;
+        let ny = (y as f32 - self.cy) * self.inv_hw;
+        let r2 = nx * nx + ny * ny;
+        if r2 >= self.outer2 { return self.ambient; }
+
+        let edge = if r2 <= self.inner2 { 1.0 }
+                   else { 1.0 - (r2 - self.inner2) / (self.outer2 - self.inner2).max(1e-6) };
+        let falloff = (1.0 - dist / self.range).clamp(0.0, 1.0);
+        let beam = edge * edge * falloff * self.intensity;
+
+        self.ambient + (1.0 - self.ambient) * beam
+    }
+}
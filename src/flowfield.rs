@@ -0,0 +1,138 @@
+// flowfield.rs
+// Campo de flujo compartido: Dijkstra desde la celda del jugador sobre todo
+// el maze. Los enemigos que persiguen solo bajan por la distancia, sin A*
+// propio. Se recalcula cuando el jugador cambia de celda o cambia el maze.
+// Hay dos: uno que cruza puertas (con coste extra) y otro que las rodea.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use crate::caster::is_passable;
+use crate::doors::{is_door, DOOR_STEP_COST};
+use crate::maze::Maze;
+
+pub const UNREACHABLE: u32 = u32::MAX;
+
+const OFFS: [(i32,i32);4] = [(1,0),(-1,0),(0,1),(0,-1)];
+
+#[derive(Default)]
+pub struct FlowField {
+    through_doors: bool,
+    w: i32,
+    h: i32,
+    dist: Vec<u32>,               // coste hasta el objetivo (UNREACHABLE si no hay camino)
+    target: Option<(i32,i32)>,
+    dirty: bool,
+    heap: BinaryHeap<Reverse<(u32, i32, i32)>>, // reutilizado entre recálculos
+}
+
+impl FlowField {
+    pub fn new(through_doors: bool) -> Self { Self { through_doors, dirty: true, ..Self::default() } }
+
+    /// Fuerza el recálculo (p.ej. se abrió una puerta o cambió el nivel).
+    pub fn invalidate(&mut self) { self.dirty = true; }
+
+    pub fn target(&self) -> Option<(i32,i32)> { self.target }
+
+    /// Recalcula si el objetivo cambió o el campo está sucio.
+    pub fn update(&mut self, maze: &Maze, target: (i32,i32)) {
+        if !self.dirty && self.target == Some(target) { return; }
+        self.dirty = false;
+        self.target = Some(target);
+
+        self.h = maze.len() as i32;
+        self.w = maze.iter().map(|r| r.len()).max().unwrap_or(0) as i32;
+        self.dist.clear();
+        self.dist.resize((self.w * self.h) as usize, UNREACHABLE);
+
+        let Some(t) = self.index(target.0, target.1) else { return; };
+        self.dist[t] = 0;
+        self.heap.clear();
+        self.heap.push(Reverse((0, target.0, target.1)));
+
+        while let Some(Reverse((d, x, y))) = self.heap.pop() {
+            if d > self.dist[(y * self.w + x) as usize] { continue; }
+            for (dx, dy) in OFFS {
+                let (nx, ny) = (x + dx, y + dy);
+                let Some(cost) = step_cost(maze, nx, ny, self.through_doors) else { continue; };
+                let k = (ny * self.w + nx) as usize;
+                let nd = d + cost;
+                if nd < self.dist[k] {
+                    self.dist[k] = nd;
+                    self.heap.push(Reverse((nd, nx, ny)));
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        (x >= 0 && y >= 0 && x < self.w && y < self.h).then(|| (y * self.w + x) as usize)
+    }
+
+    /// Coste hasta el objetivo desde `cell`.
+    #[inline]
+    pub fn distance(&self, cell: (i32,i32)) -> u32 {
+        self.index(cell.0, cell.1).map_or(UNREACHABLE, |i| self.dist[i])
+    }
+
+    /// Vecina con menor coste (None si ya está en el objetivo o no hay camino).
+    pub fn next_step(&self, cell: (i32,i32)) -> Option<(i32,i32)> {
+        let here = self.distance(cell);
+        if here == 0 || here == UNREACHABLE { return None; }
+        OFFS.iter()
+            .map(|&(dx, dy)| (cell.0 + dx, cell.1 + dy))
+            .map(|c| (self.distance(c), c))
+            .filter(|&(d, _)| d < here)
+            .min_by_key(|&(d, _)| d)
+            .map(|(_, c)| c)
+    }
+
+    /// Hasta `max_len` pasos bajando por el campo desde `cell`.
+    pub fn path_from(&self, cell: (i32,i32), max_len: usize) -> Vec<(i32,i32)> {
+        let mut path = Vec::new();
+        let mut cur = cell;
+        while path.len() < max_len {
+            let Some(n) = self.next_step(cur) else { break; };
+            path.push(n);
+            cur = n;
+        }
+        path
+    }
+}
+
+/// Coste de entrar en (x,y); None si no se puede pisar.
+#[inline]
+fn step_cost(maze: &Maze, x: i32, y: i32, through_doors: bool) -> Option<u32> {
+    if y < 0 || x < 0 { return None; }
+    let c = *maze.get(y as usize)?.get(x as usize)?;
+    if is_passable(c) { Some(1) }
+    else if through_doors && is_door(c) { Some(1 + DOOR_STEP_COST) }
+    else { None }
+}
+
+/// Los dos campos (rodeando puertas / cruzándolas) hacia la misma celda.
+pub struct Flows {
+    pub walk: FlowField,
+    pub doors: FlowField,
+}
+
+impl Flows {
+    pub fn new() -> Self {
+        Self { walk: FlowField::new(false), doors: FlowField::new(true) }
+    }
+
+    pub fn invalidate(&mut self) {
+        self.walk.invalidate();
+        self.doors.invalidate();
+    }
+
+    pub fn update(&mut self, maze: &Maze, target: (i32,i32)) {
+        self.walk.update(maze, target);
+        self.doors.update(maze, target);
+    }
+
+    /// El campo que le toca a un enemigo según sepa o no cruzar puertas.
+    pub fn for_doors(&self, through_doors: bool) -> &FlowField {
+        if through_doors { &self.doors } else { &self.walk }
+    }
+}
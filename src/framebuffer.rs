@@ -1,4 +1,107 @@
 use raylib::prelude::*;
+use std::marker::PhantomData;
+use std::sync::OnceLock;
+
+/// Número de hilos para el render por columnas (se calcula una vez).
+pub fn render_threads() -> usize {
+    static N: OnceLock<usize> = OnceLock::new();
+    *N.get_or_init(|| {
+        std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4).clamp(1, 16)
+    })
+}
+
+/// Franja vertical del framebuffer `[x0, x1)` para escribir desde un hilo.
+/// Las franjas de un mismo `strips()` no se solapan, así que cada hilo
+/// escribe columnas disjuntas sin sincronización.
+pub struct StripView<'a> {
+    base: *mut u8,
+    width: i32,
+    height: i32,
+    pub x0: i32,
+    pub x1: i32,
+    _fb: PhantomData<&'a mut Framebuffer>,
+}
+
+// Seguro: cada franja sólo escribe en sus propias columnas (ver put_pixel_rgba).
+unsafe impl Send for StripView<'_> {}
+
+impl StripView<'_> {
+    #[inline]
+    pub fn put_pixel_rgba(&self, x: i32, y: i32, r: u8, g: u8, b: u8, a: u8) {
+        if x < self.x0 || x >= self.x1 || y < 0 || y >= self.height { return; }
+        let idx = ((y as usize * self.width as usize) + x as usize) * 4;
+        unsafe {
+            *self.base.add(idx)     = r;
+            *self.base.add(idx + 1) = g;
+            *self.base.add(idx + 2) = b;
+            *self.base.add(idx + 3) = a;
+        }
+    }
+
+    /// Mezcla "source-over" sobre lo que ya hay en el píxel (resultado opaco).
+    #[inline]
+    pub fn blend_pixel(&self, x: i32, y: i32, r: u8, g: u8, b: u8, a: u8) {
+        if x < self.x0 || x >= self.x1 || y < 0 || y >= self.height { return; }
+        if a == 255 { return self.put_pixel_rgba(x, y, r, g, b, 255); }
+        let idx = ((y as usize * self.width as usize) + x as usize) * 4;
+        let t = a as u32;
+        let mix = |dst: u8, src: u8| ((src as u32 * t + dst as u32 * (255 - t) + 127) / 255) as u8;
+        unsafe {
+            let p = self.base.add(idx);
+            *p        = mix(*p, r);
+            *p.add(1) = mix(*p.add(1), g);
+            *p.add(2) = mix(*p.add(2), b);
+            *p.add(3) = 255;
+        }
+    }
+
+    /// Suma (saturada) el color escalado por `a`: brillos, fantasmas.
+    #[inline]
+    pub fn add_pixel(&self, x: i32, y: i32, r: u8, g: u8, b: u8, a: u8) {
+        if x < self.x0 || x >= self.x1 || y < 0 || y >= self.height { return; }
+        let idx = ((y as usize * self.width as usize) + x as usize) * 4;
+        let t = a as u32;
+        let add = |dst: u8, src: u8| (dst as u32 + ((src as u32 * t) >> 8)).min(255) as u8;
+        unsafe {
+            let p = self.base.add(idx);
+            *p        = add(*p, r);
+            *p.add(1) = add(*p.add(1), g);
+            *p.add(2) = add(*p.add(2), b);
+            *p.add(3) = 255;
+        }
+    }
+}
+
+/// Filtro para escalar el viewport 3D al framebuffer final.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpscaleFilter { Nearest, Bilinear }
+
+/// Resolución interna del 3D como fracción de la ventana (1.0, 0.5, 0.25...).
+#[derive(Clone, Copy)]
+pub struct RenderScale {
+    pub factor: f32,
+    pub filter: UpscaleFilter,
+}
+
+impl RenderScale {
+    /// Tamaño interno para un viewport de `w`x`h` px.
+    pub fn scaled(&self, w: i32, h: i32) -> (i32, i32) {
+        let f = self.factor.clamp(0.05, 1.0);
+        (((w as f32) * f).round().max(1.0) as i32, ((h as f32) * f).round().max(1.0) as i32)
+    }
+
+    /// Cicla 100% -> 50% -> 25% -> 100%.
+    pub fn cycle_factor(&mut self) {
+        self.factor = if self.factor > 0.75 { 0.5 } else if self.factor > 0.375 { 0.25 } else { 1.0 };
+    }
+
+    pub fn toggle_filter(&mut self) {
+        self.filter = match self.filter {
+            UpscaleFilter::Nearest  => UpscaleFilter::Bilinear,
+            UpscaleFilter::Bilinear => UpscaleFilter::Nearest,
+        };
+    }
+}
 
 pub struct Framebuffer {
     pub width: i32,
@@ -88,8 +191,81 @@ impl Framebuffer {
         }
     }
 
+    /// Parte el framebuffer en franjas verticales de `strip_w` columnas
+    /// (la última puede ser más angosta).
+    pub fn strips(&mut self, strip_w: usize) -> Vec<StripView<'_>> {
+        let strip_w = strip_w.max(1) as i32;
+        let base = self.color_buffer.data as *mut u8;
+        let mut v = Vec::new();
+        let mut x0 = 0;
+        while x0 < self.width {
+            let x1 = (x0 + strip_w).min(self.width);
+            v.push(StripView { base, width: self.width, height: self.height, x0, x1, _fb: PhantomData });
+            x0 = x1;
+        }
+        v
+    }
+
+    /// Vista de solo lectura de los píxeles RGBA8.
+    pub fn pixels(&self) -> &[u8] {
+        let len = (self.width * self.height * 4) as usize;
+        unsafe { std::slice::from_raw_parts(self.color_buffer.data as *const u8, len) }
+    }
+
+    /// Copia `src` completo escalado al rectángulo (dx,dy,dw,dh), en paralelo por franjas.
+    pub fn blit_scaled(&mut self, src: &Framebuffer, dx: i32, dy: i32, dw: i32, dh: i32, filter: UpscaleFilter) {
+        if dw <= 0 || dh <= 0 || src.width <= 0 || src.height <= 0 { return; }
+        let (sw, sh, spx) = (src.width, src.height, src.pixels());
+        let strip_w = (self.width as usize).div_ceil(render_threads());
+        std::thread::scope(|sc| {
+            for strip in self.strips(strip_w) {
+                sc.spawn(move || {
+                    let x0 = strip.x0.max(dx);
+                    let x1 = strip.x1.min(dx + dw);
+                    for y in dy..dy + dh {
+                        let v = ((y - dy) as f32 + 0.5) * sh as f32 / dh as f32 - 0.5;
+                        for x in x0..x1 {
+                            let u = ((x - dx) as f32 + 0.5) * sw as f32 / dw as f32 - 0.5;
+                            let (r, g, b, a) = match filter {
+                                UpscaleFilter::Nearest  => sample_nearest(spx, sw, sh, u, v),
+                                UpscaleFilter::Bilinear => sample_bilinear(spx, sw, sh, u, v),
+                            };
+                            strip.put_pixel_rgba(x, y, r, g, b, a);
+                        }
+                    }
+                });
+            }
+        });
+    }
+
     pub fn set_background_color(&mut self, color: Color) { self.background_color = color; }
     pub fn set_current_color(&mut self, color: Color)     { self.current_color = color; }
 
     pub fn render_to_file(&self, file_path: &str) { Image::export_image(&self.color_buffer, file_path); }
 }
+
+#[inline]
+fn sample_nearest(px: &[u8], w: i32, h: i32, u: f32, v: f32) -> (u8, u8, u8, u8) {
+    let x = (u + 0.5).floor().clamp(0.0, (w - 1) as f32) as usize;
+    let y = (v + 0.5).floor().clamp(0.0, (h - 1) as f32) as usize;
+    let i = (y * w as usize + x) * 4;
+    (px[i], px[i + 1], px[i + 2], px[i + 3])
+}
+
+#[inline]
+fn sample_bilinear(px: &[u8], w: i32, h: i32, u: f32, v: f32) -> (u8, u8, u8, u8) {
+    let u = u.clamp(0.0, (w - 1) as f32);
+    let v = v.clamp(0.0, (h - 1) as f32);
+    let (x0, y0) = (u.floor() as usize, v.floor() as usize);
+    let x1 = (x0 + 1).min(w as usize - 1);
+    let y1 = (y0 + 1).min(h as usize - 1);
+    let (fx, fy) = (u - x0 as f32, v - y0 as f32);
+
+    let at = |x: usize, y: usize, c: usize| px[(y * w as usize + x) * 4 + c] as f32;
+    let mix = |c: usize| {
+        let top = at(x0, y0, c) * (1.0 - fx) + at(x1, y0, c) * fx;
+        let bot = at(x0, y1, c) * (1.0 - fx) + at(x1, y1, c) * fx;
+        (top * (1.0 - fy) + bot * fy) as u8
+    };
+    (mix(0), mix(1), mix(2), mix(3))
+}
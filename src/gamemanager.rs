@@ -1,6 +1,45 @@
 // gamemanager.rs
 use raylib::prelude::Vector2;
 
+/// Reglas de escalada de un nivel: cuanto más dura la partida, peor.
+#[derive(Clone, Copy)]
+pub struct Escalation {
+    pub speed_per_min: f32,   // +fracción de velocidad de los enemigos por minuto
+    pub speed_max: f32,       // tope del multiplicador de velocidad
+    pub wave_after: f32,      // seg hasta la primera oleada (0 = sin oleadas)
+    pub wave_every: f32,      // seg entre oleadas siguientes
+    pub wave_size: usize,     // enemigos por oleada
+    pub spawn_per_key: usize, // enemigos extra por cada llave recogida
+    pub max_enemies: usize,   // nunca más que esto a la vez
+    pub spawn_min_cells: f32, // no aparecen más cerca del jugador que esto
+    pub hunt_speed: f32,      // multiplicador extra en la fase de caza
+}
+
+impl Default for Escalation {
+    fn default() -> Self {
+        Self {
+            speed_per_min: 0.10,
+            speed_max: 1.5,
+            wave_after: 120.0,
+            wave_every: 90.0,
+            wave_size: 1,
+            spawn_per_key: 0,
+            max_enemies: 4,
+            spawn_min_cells: 8.0,
+            hunt_speed: 1.2,
+        }
+    }
+}
+
+/// Eventos de la escalada que el bucle principal tiene que aplicar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameEvent {
+    /// Aparecen `n` enemigos más
+    Spawn(usize),
+    /// El jugador tiene todas las llaves: todos saben dónde está
+    HuntStarted,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum GameState {
     Playing,
@@ -14,6 +53,12 @@ pub struct GameManager {
     trigger_dist: f32,
     jumpscare_left: f32,
     jumpscare_total: f32,
+
+    pub escalation: Escalation,
+    pub level_time: f32, // seg jugados en este intento (solo cuenta en Playing)
+    pub hunt: bool,
+    next_wave: f32,
+    events: Vec<GameEvent>,
 }
 
 impl GameManager {
@@ -23,12 +68,51 @@ impl GameManager {
             trigger_dist,
             jumpscare_left: 0.0,
             jumpscare_total: jumpscare_seconds,
+            escalation: Escalation::default(),
+            level_time: 0.0,
+            hunt: false,
+            next_wave: Escalation::default().wave_after,
+            events: Vec::new(),
         }
     }
 
     pub fn reset(&mut self) {
         self.state = GameState::Playing;
         self.jumpscare_left = 0.0;
+        self.level_time = 0.0;
+        self.hunt = false;
+        self.next_wave = self.escalation.wave_after;
+        self.events.clear();
+    }
+
+    /// Cambia las reglas de escalada (al elegir nivel) y reinicia el reloj.
+    pub fn set_escalation(&mut self, esc: Escalation) {
+        self.escalation = esc;
+        self.reset();
+    }
+
+    /// Multiplicador de velocidad de los enemigos según el tiempo (y la caza).
+    pub fn speed_mult(&self) -> f32 {
+        let esc = &self.escalation;
+        let t = 1.0 + esc.speed_per_min * self.level_time / 60.0;
+        let t = t.min(esc.speed_max.max(1.0));
+        if self.hunt { t * esc.hunt_speed } else { t }
+    }
+
+    /// Llamar al recoger una llave. `has_all`: ya tiene todas.
+    pub fn on_key_picked(&mut self, has_all: bool) {
+        if self.escalation.spawn_per_key > 0 {
+            self.events.push(GameEvent::Spawn(self.escalation.spawn_per_key));
+        }
+        if has_all && !self.hunt {
+            self.hunt = true;
+            self.events.push(GameEvent::HuntStarted);
+        }
+    }
+
+    /// Eventos pendientes (se vacía la cola).
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
     }
 
     /// Llama esto una vez por frame para avanzar la lógica de estados.
@@ -43,6 +127,14 @@ impl GameManager {
     ) {
         match self.state {
             GameState::Playing => {
+                // Reloj del nivel y oleadas
+                self.level_time += dt;
+                let esc = self.escalation;
+                if esc.wave_after > 0.0 && self.level_time >= self.next_wave {
+                    self.next_wave += esc.wave_every.max(1.0);
+                    self.events.push(GameEvent::Spawn(esc.wave_size));
+                }
+
                 // Si algún enemigo está a menos de trigger_dist, disparamos JumpScare
                 for epos in enemy_positions {
                     let d2 = (epos.x - player_pos.x).powi(2) + (epos.y - player_pos.y).powi(2);
@@ -9,6 +9,7 @@ use crate::player::Player;
 use crate::enemy::Enemy;
 use crate::sprites::Sprite;
 use crate::level::{LevelTheme, minimap_colors_for};
+use crate::registry::{self, Category, ItemId, KeyMode};
 
 /// Dirección de los slots de llaves
 #[derive(Clone, Copy)]
@@ -48,8 +49,10 @@ pub struct KeySlotsConfig {
     pub offset_x: i32,        // ajuste fino X
     pub offset_y: i32,        // ajuste fino Y
     pub style: KeySlotsStyle, // mantiene icon_inset
-    /// Orden de los iconos (texturas HUD): 'y' (amarilla), 'b' (azul), 'r' (roja)
-    pub order: [char; 3],
+    /// Ancho máximo de la fila: con más llaves los iconos se encogen
+    pub max_w: i32,
+    /// Tamaño del dígito de cantidad (fracción del icono), si `Hud::key_counts`
+    pub count_frac: f32,
 }
 
 /// Configuración para los dígitos FPS (posición/tamaño/espaciado)
@@ -68,6 +71,56 @@ pub struct FpsDigitsConfig {
     pub align_right: bool,
 }
 
+/// Configuración de la barra de batería de la linterna
+#[derive(Clone, Copy)]
+pub struct BatteryBarConfig {
+    pub w: i32,               // ancho de la barra
+    pub h: i32,               // alto de la barra
+    pub border_px: i32,       // grosor del marco
+    pub anchor: KeySlotsAnchor,
+    pub pad: i32,             // margen desde la esquina del HUD
+    pub offset_x: i32,        // ajuste fino X
+    pub offset_y: i32,        // ajuste fino Y
+    pub frame:    (u8,u8,u8,u8),
+    pub bg:       (u8,u8,u8,u8),
+    pub full:     (u8,u8,u8,u8), // > 50%
+    pub mid:      (u8,u8,u8,u8), // > 20%
+    pub low:      (u8,u8,u8,u8), // <= 20%
+    pub off_dim:  f32,           // multiplicador del relleno con la luz apagada
+}
+
+/// Configuración del medidor de sigilo (lo visible que está el jugador)
+#[derive(Clone, Copy)]
+pub struct StealthMeterConfig {
+    pub w: i32,
+    pub h: i32,
+    pub border_px: i32,
+    pub anchor: KeySlotsAnchor,
+    pub pad: i32,
+    pub offset_x: i32,
+    pub offset_y: i32,
+    pub frame:   (u8,u8,u8,u8),
+    pub bg:      (u8,u8,u8,u8),
+    pub hidden:  (u8,u8,u8,u8), // relleno con visibilidad baja
+    pub exposed: (u8,u8,u8,u8), // relleno con visibilidad alta
+    pub alert:   (u8,u8,u8,u8), // marco si algún enemigo te ha detectado
+}
+
+/// Configuración del hueco del consumible seleccionado (icono + cantidad)
+#[derive(Clone, Copy)]
+pub struct ItemSlotConfig {
+    pub slot: i32,            // tamaño del icono
+    pub digit: i32,           // tamaño de los dígitos de la cantidad
+    pub anchor: KeySlotsAnchor,
+    pub pad: i32,
+    pub offset_x: i32,
+    pub offset_y: i32,
+    pub frame: (u8,u8,u8,u8),
+    pub bg:    (u8,u8,u8,u8),
+    pub pip:       (u8,u8,u8,u8), // marca de los otros tipos que llevas
+    pub pip_empty: (u8,u8,u8,u8),
+}
+
 /// HUD de 128px, textura de fondo 'h', cara 'f', minimapa, llaves y FPS.
 pub struct Hud {
     pub height: i32,          // px
@@ -88,10 +141,23 @@ pub struct Hud {
 
     /// Llaves (solo sprites con alpha, sin fondos)
     pub key_cfg: KeySlotsConfig,
+    /// Cantidad bajo cada llave (llaves que se gastan, ver `KeyMode`)
+    pub key_counts: bool,
+    /// Colores de llave del nivel: con `key_counts` salen todos, aunque no lleves ninguna
+    pub level_keys: Vec<ItemId>,
 
     /// FPS digits config
     pub fps_cfg: FpsDigitsConfig,
 
+    /// Batería de la linterna
+    pub battery_cfg: BatteryBarConfig,
+
+    /// Medidor de sigilo
+    pub stealth_cfg: StealthMeterConfig,
+
+    /// Consumible seleccionado
+    pub item_cfg: ItemSlotConfig,
+
     // --- Estado interno para FPS (suavizado/refresh) ---
     fps_accum_time: f32,
     fps_accum_frames: u32,
@@ -135,8 +201,11 @@ impl Hud {
                     border_px:    0,
                     icon_inset:   0,
                 },
-                order: ['y', 'b', 'r'],
+                max_w: 316, // 3 iconos de 100 + huecos
+                count_frac: 0.34,
             },
+            key_counts: false,
+            level_keys: Vec::new(),
 
             // FPS: 3 dígitos, arriba-derecha por defecto (tú lo ajustas)
             fps_cfg: FpsDigitsConfig {
@@ -151,6 +220,53 @@ impl Hud {
                 align_right: true,
             },
 
+            // Batería: debajo de los dígitos FPS
+            battery_cfg: BatteryBarConfig {
+                w: 190,
+                h: 20,
+                border_px: 2,
+                anchor: KeySlotsAnchor::TopRight,
+                pad: 10,
+                offset_x: -626,
+                offset_y: 82,
+                frame: (0, 0, 0, 220),
+                bg:    (30, 30, 30, 200),
+                full:  (70, 200, 70, 255),
+                mid:   (230, 200, 50, 255),
+                low:   (220, 50, 40, 255),
+                off_dim: 0.45,
+            },
+
+            // Sigilo: barra fina debajo de la batería
+            stealth_cfg: StealthMeterConfig {
+                w: 190,
+                h: 10,
+                border_px: 2,
+                anchor: KeySlotsAnchor::TopRight,
+                pad: 10,
+                offset_x: -626,
+                offset_y: 106,
+                frame:   (0, 0, 0, 220),
+                bg:      (20, 20, 30, 200),
+                hidden:  (60, 90, 160, 255),
+                exposed: (240, 240, 220, 255),
+                alert:   (220, 40, 30, 255),
+            },
+
+            // Consumible: entre la batería y la cara
+            item_cfg: ItemSlotConfig {
+                slot: 60,
+                digit: 22,
+                anchor: KeySlotsAnchor::TopRight,
+                pad: 10,
+                offset_x: -560,
+                offset_y: 6,
+                frame: (0, 0, 0, 220),
+                bg:    (25, 25, 25, 180),
+                pip:       (230, 220, 180, 255),
+                pip_empty: (70, 70, 70, 255),
+            },
+
             // estado FPS
             fps_accum_time: 0.0,
             fps_accum_frames: 0,
@@ -202,6 +318,7 @@ impl Hud {
         player: &Player,
         enemies: &[Enemy],
         keys_sprites: &[Sprite], // para pintar llaves en minimapa
+        stealth: f32,            // visibilidad del jugador (0..1)
         block_size: usize,
     ) {
         let w = fb.width as i32;
@@ -243,14 +360,131 @@ impl Hud {
             self.minimap_cells_w, self.minimap_cells_h, &self.minimap_style,
         );
 
-        // 4) Llaves (solo sprites con alpha + blending)
+        // 4) Llaves y objetos de misión (solo sprites con alpha + blending)
         self.render_key_icons_only(fb, tex, player, w, y0);
 
         // 5) FPS (dígitos desde spritesheet 'n' 0..9; sólo números)
         self.render_fps_digits(fb, tex, w, y0);
+
+        // 6) Batería de la linterna
+        self.render_battery_bar(fb, player, w, y0);
+
+        // 7) Sigilo
+        let detected = enemies.iter().any(|e| e.awareness >= 1.0);
+        self.render_stealth_meter(fb, stealth, detected, w, y0);
+
+        // 8) Consumible seleccionado
+        self.render_item_slot(fb, tex, player, w, y0);
     }
 
-    /// Llaves: ÚNICAMENTE los sprites de las llaves presentes (alpha blending).
+    /// Icono del consumible seleccionado con su cantidad debajo,
+    /// y una marca por tipo (encendida si llevas alguno).
+    fn render_item_slot(
+        &self,
+        fb: &mut Framebuffer,
+        tex: &TextureManager,
+        player: &Player,
+        screen_w: i32,
+        hud_y0: i32,
+    ) {
+        let cfg = self.item_cfg;
+        let slot = cfg.slot.max(8);
+
+        let (x, y) = anchor_origin(cfg.anchor, cfg.pad, slot, slot, screen_w, hud_y0, self.height);
+        let (x, y) = (x + cfg.offset_x, y + cfg.offset_y);
+
+        fill_rect(fb, x - 2, y - 2, slot + 4, slot + 4, cfg.frame);
+        fill_rect(fb, x, y, slot, slot, cfg.bg);
+
+        let selected = player.inv.selected;
+        let count = selected.map_or(0, |id| player.inv.count(id));
+        if let Some(id) = selected && count > 0 {
+            blit_image_to_rect_over(fb, tex, registry::def(id).icon, x, y, slot, slot);
+        }
+
+        // Cantidad (un dígito: las pilas del registro no pasan de 9)
+        let d = cfg.digit.max(4);
+        blit_sheet_frame_to_rect_over(fb, tex, 'n', count.min(9) as usize, x + (slot - d) / 2, y + slot + 4, d, d);
+
+        // Marcas por tipo de consumible (la seleccionada, más alta)
+        let kinds: Vec<_> = registry::of_category(Category::Consumable).collect();
+        let n = kinds.len().max(1) as i32;
+        let pip_w = (slot - 4 * (n - 1)) / n;
+        for (i, &k) in kinds.iter().enumerate() {
+            let px = x + i as i32 * (pip_w + 4);
+            let ph = if Some(k) == selected { 6 } else { 3 };
+            let c = if player.inv.has(k) { cfg.pip } else { cfg.pip_empty };
+            fill_rect(fb, px, y - 4 - ph, pip_w, ph, c);
+        }
+    }
+
+    /// Medidor de sigilo: relleno de azul (oculto) a blanco (expuesto);
+    /// el marco se pone rojo si algún enemigo te ha detectado.
+    fn render_stealth_meter(
+        &self,
+        fb: &mut Framebuffer,
+        level: f32,
+        detected: bool,
+        screen_w: i32,
+        hud_y0: i32,
+    ) {
+        let cfg = self.stealth_cfg;
+        let bw = cfg.w.max(4);
+        let bh = cfg.h.max(4);
+        let border = cfg.border_px.clamp(0, bh / 2 - 1);
+
+        let (x, y) = anchor_origin(cfg.anchor, cfg.pad, bw, bh, screen_w, hud_y0, self.height);
+        let (x, y) = (x + cfg.offset_x, y + cfg.offset_y);
+
+        fill_rect(fb, x, y, bw, bh, if detected { cfg.alert } else { cfg.frame });
+        let (ix, iy) = (x + border, y + border);
+        let (iw, ih) = (bw - border * 2, bh - border * 2);
+        fill_rect(fb, ix, iy, iw, ih, cfg.bg);
+
+        let level = level.clamp(0.0, 1.0);
+        let fill_w = (iw as f32 * level).round() as i32;
+        if fill_w <= 0 { return; }
+
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * level) as u8;
+        let (h, e) = (cfg.hidden, cfg.exposed);
+        let color = (mix(h.0, e.0), mix(h.1, e.1), mix(h.2, e.2), mix(h.3, e.3));
+        fill_rect(fb, ix, iy, fill_w, ih, color);
+    }
+
+    /// Barra de batería: marco + relleno proporcional (verde/amarillo/rojo),
+    /// atenuado si la linterna está apagada.
+    fn render_battery_bar(
+        &self,
+        fb: &mut Framebuffer,
+        player: &Player,
+        screen_w: i32,
+        hud_y0: i32,
+    ) {
+        let cfg = self.battery_cfg;
+        let bw = cfg.w.max(4);
+        let bh = cfg.h.max(4);
+        let border = cfg.border_px.clamp(0, bh / 2 - 1);
+
+        let (x, y) = anchor_origin(cfg.anchor, cfg.pad, bw, bh, screen_w, hud_y0, self.height);
+        let (x, y) = (x + cfg.offset_x, y + cfg.offset_y);
+
+        fill_rect(fb, x, y, bw, bh, cfg.frame);
+        let (ix, iy) = (x + border, y + border);
+        let (iw, ih) = (bw - border * 2, bh - border * 2);
+        fill_rect(fb, ix, iy, iw, ih, cfg.bg);
+
+        let level = player.light.battery.clamp(0.0, 1.0);
+        let fill_w = (iw as f32 * level).round() as i32;
+        if fill_w <= 0 { return; }
+
+        let (r, g, b, a) = if level > 0.5 { cfg.full } else if level > 0.2 { cfg.mid } else { cfg.low };
+        let k = if player.light.on { 1.0 } else { cfg.off_dim };
+        let color = ((r as f32 * k) as u8, (g as f32 * k) as u8, (b as f32 * k) as u8, a);
+        fill_rect(fb, ix, iy, fill_w, ih, color);
+    }
+
+    /// Iconos (alpha blending, orden del registro) de las llaves y objetos de misión
+    /// que llevas; con `key_counts`, todos los colores del nivel con su cantidad (también 0).
     fn render_key_icons_only(
         &self,
         fb: &mut Framebuffer,
@@ -261,12 +495,24 @@ impl Hud {
     ) {
         let cfg = self.key_cfg;
 
-        let slot = cfg.slot.max(8);
+        // Con llaves que se gastan, un hueco por color del nivel (también a 0)
+        let held: Vec<(char, u8)> = if self.key_counts {
+            self.level_keys.iter().map(|&id| (registry::def(id).icon, player.inv.count(id))).collect()
+        } else {
+            player.inv.held()
+                .map(|(id, n)| (registry::def(id), n))
+                .filter(|(d, _)| matches!(d.category, Category::Key | Category::Quest))
+                .map(|(d, n)| (d.icon, n))
+                .collect()
+        };
+        if held.is_empty() { return; }
+
+        let n = held.len() as i32;
         let gap  = cfg.gap.max(0);
+        let slot = cfg.slot.min((cfg.max_w - gap * (n - 1)) / n).max(8);
         let pad  = cfg.pad.max(0);
         let inset = cfg.style.icon_inset.clamp(0, slot / 3);
 
-        let n = 3;
         let (content_w, content_h) = match cfg.dir {
             KeySlotsDirection::Row    => (slot * n + gap * (n - 1), slot),
             KeySlotsDirection::Column => (slot,         slot * n + gap * (n - 1)),
@@ -295,25 +541,22 @@ impl Hud {
         sx0 += cfg.offset_x;
         sy0 += cfg.offset_y;
 
-        for (idx, key_ch) in cfg.order.iter().enumerate() {
+        let d = ((slot as f32 * cfg.count_frac) as i32).max(4);
+        for (idx, &(icon, count)) in held.iter().enumerate() {
             let (x, y) = match cfg.dir {
                 KeySlotsDirection::Row    => (sx0 + idx as i32 * (slot + gap), sy0),
                 KeySlotsDirection::Column => (sx0, sy0 + idx as i32 * (slot + gap)),
             };
 
-            let has_key = match *key_ch {
-                'y' => player.inv.key_yellow,
-                'b' => player.inv.key_blue,
-                'r' => player.inv.key_red,
-                _   => false,
-            };
+            blit_image_to_rect_over(
+                fb, tex, icon,
+                x + inset, y + inset,
+                slot - inset * 2, slot - inset * 2
+            );
 
-            if has_key {
-                blit_image_to_rect_over(
-                    fb, tex, *key_ch,
-                    x + inset, y + inset,
-                    slot - inset * 2, slot - inset * 2
-                );
+            // Cantidad en la esquina inferior derecha (un dígito: pila máx. 9)
+            if self.key_counts {
+                blit_sheet_frame_to_rect_over(fb, tex, 'n', count.min(9) as usize, x + slot - d, y + slot - d, d, d);
             }
         }
     }
@@ -399,6 +642,7 @@ impl Hud {
 
     pub fn apply_theme(&mut self, theme: &LevelTheme) {
         self.minimap_style = minimap_colors_for(theme);
+        self.key_counts = theme.key_mode == KeyMode::Consumed;
     }
 }
 
@@ -406,6 +650,17 @@ impl Hud {
 // Helpers de blit/fill (+ alpha blending)
 // -----------------------------------------------------------------------------
 
+/// Esquina superior-izquierda de un rect `w`x`h` anclado en el HUD (sin offsets).
+fn anchor_origin(anchor: KeySlotsAnchor, pad: i32, w: i32, h: i32, screen_w: i32, hud_y0: i32, hud_h: i32) -> (i32, i32) {
+    match anchor {
+        KeySlotsAnchor::BottomRight => (screen_w - pad - w, hud_y0 + hud_h - pad - h),
+        KeySlotsAnchor::BottomLeft  => (pad, hud_y0 + hud_h - pad - h),
+        KeySlotsAnchor::TopRight    => (screen_w - pad - w, hud_y0 + pad),
+        KeySlotsAnchor::TopLeft     => (pad, hud_y0 + pad),
+        KeySlotsAnchor::Custom { x, y } => (x, y),
+    }
+}
+
 /// Copia una imagen a rectángulo con alpha BLENDING (source-over) sobre el framebuffer.
 pub fn blit_image_to_rect_over(
     fb: &mut Framebuffer,
@@ -0,0 +1,230 @@
+// items.rs
+// Consumibles que deja el generador por el maze:
+// - bengala: aturde a los enemigos que la ven de cerca mientras arde
+// - cascabel: se lanza hacia donde miras y suena al caer (atrae enemigos)
+// - amuleto: sella unos segundos la celda de pasillo que tienes delante
+// Q usa el seleccionado, TAB cambia de objeto. Qué se puede llevar y
+// cuántos está en registry.rs; aquí solo lo que hacen al usarse.
+
+use raylib::prelude::Vector2;
+use crate::caster::{is_passable, line_of_sight, raycast};
+use crate::doors::is_door;
+use crate::enemy::Enemy;
+use crate::maze::Maze;
+use crate::player::Player;
+use crate::sprites::{Sprite, SpriteBlend};
+use crate::textures::TextureManager;
+use crate::utils_grid::world_to_cell;
+
+/// Glyphs de los consumibles en el maze (y claves de textura del sprite).
+pub const FLARE_GLYPH: char = '*';
+pub const LURE_GLYPH: char = '?';
+pub const WARD_GLYPH: char = '&';
+/// Textura de la bengala encendida.
+pub const FLARE_LIT: char = 'F';
+/// Barrera del amuleto: bloque transparente (se ve a través, no se pasa).
+pub const WARD_WALL: char = '~';
+
+/// Efecto de un consumible al usarlo.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemKind {
+    Flare,
+    Lure,
+    Ward,
+}
+
+#[derive(Clone, Copy)]
+pub struct ItemConfig {
+    pub flare_radius: f32,   // celdas
+    pub flare_stun: f32,     // seg aturdidos al encenderla
+    pub flare_burn: f32,     // seg que arde (y sigue aturdiendo a quien se acerque)
+    pub lure_range: f32,     // celdas máximas del lanzamiento
+    pub lure_speed: f32,     // celdas/seg en vuelo
+    pub lure_rings: u32,     // veces que suena una vez en el suelo
+    pub lure_interval: f32,  // seg entre sonidos
+    pub ward_time: f32,      // seg que dura la barrera
+}
+
+impl Default for ItemConfig {
+    fn default() -> Self {
+        Self {
+            flare_radius: 3.0,
+            flare_stun: 4.0,
+            flare_burn: 6.0,
+            lure_range: 7.0,
+            lure_speed: 9.0,
+            lure_rings: 3,
+            lure_interval: 1.5,
+            ward_time: 10.0,
+        }
+    }
+}
+
+/// Qué pasó con los objetos (para SFX, ruido y replanificar).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ItemEvent {
+    FlareLit,
+    LureThrown,
+    /// El cascabel suena en `pos` (al caer y cada `lure_interval`)
+    LureRing { pos: Vector2 },
+    WardUp { cell: (i32,i32) },
+    WardDown { cell: (i32,i32) },
+}
+
+struct Flare { pos: Vector2, left: f32 }
+
+struct Lure {
+    pos: Vector2,
+    vel: Vector2,     // px/seg (cero en el suelo)
+    flight: f32,      // seg de vuelo restantes
+    rings: u32,       // sonidos que le quedan
+    next_ring: f32,
+}
+
+struct Ward { cell: (i32,i32), left: f32 }
+
+/// Objetos ya usados que siguen activos en el mundo.
+pub struct Items {
+    pub cfg: ItemConfig,
+    flares: Vec<Flare>,
+    lures: Vec<Lure>,
+    wards: Vec<Ward>,
+}
+
+impl Items {
+    pub fn new(cfg: ItemConfig) -> Self {
+        Self { cfg, flares: Vec::new(), lures: Vec::new(), wards: Vec::new() }
+    }
+
+    /// Las barreras viven en el maze; el reset del nivel ya lo restaura.
+    pub fn reset(&mut self) {
+        self.flares.clear();
+        self.lures.clear();
+        self.wards.clear();
+    }
+
+    /// Usa `kind` desde la posición del jugador. None = no se pudo (no se gasta).
+    pub fn use_item(
+        &mut self,
+        kind: ItemKind,
+        maze: &mut Maze,
+        player: &Player,
+        enemies: &mut [Enemy],
+        block_size: usize,
+    ) -> Option<ItemEvent> {
+        let bs = block_size as f32;
+        let dir = Vector2::new(player.a.cos(), player.a.sin());
+        match kind {
+            ItemKind::Flare => {
+                let pos = player.pos;
+                self.stun_near(pos, self.cfg.flare_stun, maze, enemies, block_size);
+                self.flares.push(Flare { pos, left: self.cfg.flare_burn });
+                Some(ItemEvent::FlareLit)
+            }
+            ItemKind::Lure => {
+                // Cae un poco antes del primer muro (o al máximo alcance)
+                let range = self.cfg.lure_range * bs;
+                let dist = raycast(maze, player.pos, dir, range, block_size, |c| !is_passable(c))
+                    .map_or(range, |hit| (hit.distance - bs * 0.3).max(0.0));
+                let speed = self.cfg.lure_speed * bs;
+                self.lures.push(Lure {
+                    pos: player.pos,
+                    vel: dir * speed,
+                    flight: dist / speed.max(1e-3),
+                    rings: self.cfg.lure_rings.max(1),
+                    next_ring: 0.0,
+                });
+                Some(ItemEvent::LureThrown)
+            }
+            ItemKind::Ward => {
+                // La celda de pasillo de delante: ni la del jugador, ni puertas, ni con enemigo dentro
+                let here = world_to_cell(player.pos.x, player.pos.y, block_size);
+                let ahead = player.pos + dir * bs;
+                let cell = world_to_cell(ahead.x, ahead.y, block_size);
+                if cell == here || cell.0 < 0 || cell.1 < 0 { return None; }
+                let c = *maze.get(cell.1 as usize)?.get(cell.0 as usize)?;
+                if !is_passable(c) || is_door(c) { return None; }
+                if enemies.iter().any(|e| world_to_cell(e.pos.x, e.pos.y, block_size) == cell) { return None; }
+
+                maze[cell.1 as usize][cell.0 as usize] = WARD_WALL;
+                self.wards.push(Ward { cell, left: self.cfg.ward_time });
+                Some(ItemEvent::WardUp { cell })
+            }
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        dt: f32,
+        maze: &mut Maze,
+        enemies: &mut [Enemy],
+        block_size: usize,
+    ) -> Vec<ItemEvent> {
+        let mut events = Vec::new();
+
+        // Bengalas: mientras arden nadie se acerca sin quedarse deslumbrado
+        for i in 0..self.flares.len() {
+            let pos = self.flares[i].pos;
+            self.stun_near(pos, 0.5, maze, enemies, block_size);
+            self.flares[i].left -= dt;
+        }
+        self.flares.retain(|f| f.left > 0.0);
+
+        // Cascabeles: vuelan, caen y suenan unas cuantas veces
+        for l in self.lures.iter_mut() {
+            if l.flight > 0.0 {
+                let step = dt.min(l.flight);
+                l.pos += l.vel * step;
+                l.flight -= step;
+                continue;
+            }
+            l.next_ring -= dt;
+            if l.next_ring <= 0.0 && l.rings > 0 {
+                l.rings -= 1;
+                l.next_ring = self.cfg.lure_interval;
+                events.push(ItemEvent::LureRing { pos: l.pos });
+            }
+        }
+        self.lures.retain(|l| l.rings > 0 || l.next_ring > 0.0);
+
+        // Amuletos: al acabarse la celda vuelve a ser pasillo
+        for w in self.wards.iter_mut() {
+            w.left -= dt;
+            if w.left <= 0.0 {
+                let (i, j) = (w.cell.0 as usize, w.cell.1 as usize);
+                if maze[j][i] == WARD_WALL { maze[j][i] = ' '; }
+                events.push(ItemEvent::WardDown { cell: w.cell });
+            }
+        }
+        self.wards.retain(|w| w.left > 0.0);
+
+        events
+    }
+
+    /// Aturde `secs` a los enemigos a menos de `flare_radius` con vista directa.
+    fn stun_near(&self, pos: Vector2, secs: f32, maze: &Maze, enemies: &mut [Enemy], block_size: usize) {
+        let reach = self.cfg.flare_radius * block_size as f32;
+        for e in enemies.iter_mut() {
+            if (e.pos - pos).length() <= reach && line_of_sight(maze, pos, e.pos, block_size) {
+                e.stun(secs);
+            }
+        }
+    }
+
+    /// Sprites de bengalas encendidas y cascabeles (para el render 3D).
+    pub fn sprites(&self, tex: &TextureManager) -> Vec<Sprite> {
+        let mut v = Vec::with_capacity(self.flares.len() + self.lures.len());
+        let frames = tex.sheet_frames(FLARE_LIT);
+        for (k, f) in self.flares.iter().enumerate() {
+            let mut s = Sprite::new_animated(f.pos, FLARE_LIT, 1.0, frames, 12.0, k);
+            s.blend = SpriteBlend::Additive;
+            s.opacity = f.left.min(1.0); // se apaga el último segundo
+            v.push(s);
+        }
+        for l in &self.lures {
+            v.push(Sprite::new_animated(l.pos, LURE_GLYPH, 1.0, 1, 0.0, 0));
+        }
+        v
+    }
+}
+
@@ -1,12 +1,67 @@
 use raylib::color::Color;
 use crate::renderer::MinimapColors;
+use crate::textures::SheetLayout;
+use crate::postfx::ColorGrade;
+use crate::gamemanager::Escalation;
+use crate::registry::KeyMode;
 
 #[derive(Clone)]
 pub struct Lighting {
     pub shade_min: f32, // mínimo multiplicador de luz (0..1)
     pub atten: f32,     // atenuación por distancia (recomendado ~0.001..0.003)
+    pub fog: Option<Fog>, // niebla de color (None = sin niebla)
 }
 
+/// Curva de densidad de la niebla. Distancias en celdas.
+#[derive(Clone, Copy)]
+pub enum FogCurve {
+    /// 0 antes de `start`, 1 a partir de `end`
+    Linear { start: f32, end: f32 },
+    /// 1 - e^(-d·density)
+    Exp { density: f32 },
+    /// 1 - e^(-(d·density)²)
+    Exp2 { density: f32 },
+}
+
+/// Niebla por tema: mezcla paredes/sprites/suelo hacia `color` según la distancia.
+#[derive(Clone, Copy)]
+pub struct Fog {
+    pub color: Color,
+    pub curve: FogCurve,
+    pub max: f32,          // opacidad máxima de la niebla (0..1)
+    pub tint_sky: bool,    // si true, el cielo también se funde con la niebla
+    pub tint_minimap: f32, // cuánto se tiñe la paleta del minimapa (0..1)
+}
+
+impl Fog {
+    /// Factor de niebla (0 = nítido, `max` = todo niebla) a `dist_cells` celdas.
+    #[inline]
+    pub fn factor(&self, dist_cells: f32) -> f32 {
+        let d = dist_cells.max(0.0);
+        let f = match self.curve {
+            FogCurve::Linear { start, end } => ((d - start) / (end - start).max(1e-4)).clamp(0.0, 1.0),
+            FogCurve::Exp { density }       => 1.0 - (-d * density).exp(),
+            FogCurve::Exp2 { density }      => 1.0 - (-(d * density) * (d * density)).exp(),
+        };
+        f * self.max.clamp(0.0, 1.0)
+    }
+
+    /// Mezcla un color ya sombreado (multiplicado por `light`) hacia la niebla,
+    /// que también recibe la misma luz (se ve donde alumbra la linterna).
+    #[inline]
+    pub fn blend(&self, r: u8, g: u8, b: u8, light: f32, f: f32) -> (u8, u8, u8) {
+        let inv = 1.0 - f;
+        (
+            (r as f32 * light * inv + self.color.r as f32 * light * f) as u8,
+            (g as f32 * light * inv + self.color.g as f32 * light * f) as u8,
+            (b as f32 * light * inv + self.color.b as f32 * light * f) as u8,
+        )
+    }
+}
+
+/// Clave en TextureManager del cielo panorámico del tema.
+pub const SKY_GLYPH: char = 'S';
+
 #[derive(Clone)]
 pub struct LevelTheme {
     // PNGs (por nivel) para walls y personajes/overlays:
@@ -14,9 +69,16 @@ pub struct LevelTheme {
     pub wall2: &'static str, // '@'
     pub wall3: &'static str, // '#'
     pub wall4: &'static str, // '!'
-    pub enemy: &'static str, // 'e' (spritesheet o imagen)
+    pub enemy: &'static str, // 'e' (spritesheet, direccional si enemy_sheet lo dice)
     pub jumps: &'static str, // 'j' overlay
 
+    // Layout de hoja de cada PNG anterior (STATIC = imagen simple)
+    pub wall1_sheet: SheetLayout,
+    pub wall2_sheet: SheetLayout,
+    pub wall3_sheet: SheetLayout,
+    pub wall4_sheet: SheetLayout,
+    pub enemy_sheet: SheetLayout,
+
     // Música
     pub music_game: &'static str,
     pub music_jump: &'static str,
@@ -27,10 +89,20 @@ pub struct LevelTheme {
     // Cielo/suelo
     pub sky:   Color,
     pub floor: Color,
+    pub sky_tex: Option<&'static str>, // 'S' panorama 360° (si None, cielo plano `sky`)
 
     // Sombreado del mundo y sprites
     pub lighting: Lighting,
 
+    // Grading de color del post-procesado
+    pub grade: ColorGrade,
+
+    // Escalada: velocidad con el tiempo, oleadas, caza final
+    pub escalation: Escalation,
+
+    // Llaves: maestras o de un solo uso (el generador cuadra llaves y puertas)
+    pub key_mode: KeyMode,
+
     // UI imágenes menú
     pub img_logo:  &'static str, // 'O'
     pub img_card1: &'static str, // 'A'
@@ -46,7 +118,13 @@ pub struct LevelTheme {
 }
 
 pub fn minimap_colors_for(theme: &LevelTheme) -> MinimapColors {
-    let c = |C: Color| (C.r, C.g, C.b, C.a);
+    // Tinte opcional del minimapa hacia el color de la niebla
+    let (fog_c, t) = match theme.lighting.fog {
+        Some(f) => (f.color, f.tint_minimap.clamp(0.0, 1.0)),
+        None    => (Color::BLACK, 0.0),
+    };
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+    let c = |C: Color| (mix(C.r, fog_c.r), mix(C.g, fog_c.g), mix(C.b, fog_c.b), C.a);
     MinimapColors {
         wall1:   c(theme.mini_wall1),
         wall2:   c(theme.mini_wall2),
@@ -61,9 +139,6 @@ pub fn minimap_colors_for(theme: &LevelTheme) -> MinimapColors {
         dir_line: (180, 255, 180, 255),
         fov_ray:  (110, 160, 255, 160),
         frame:    (0, 0, 0, 160),
-        key_y:    (255, 215, 0, 255),
-        key_b:    (60, 130, 255, 255),
-        key_r:    (235, 60, 60, 255),
     }
 }
 
@@ -74,9 +149,15 @@ pub fn theme_for(level: u8) -> LevelTheme {
             wall2: "assets/level1/wall2.png",
             wall3: "assets/level1/wall3.png",
             wall4: "assets/level1/wall4.png",
-            enemy: "assets/level1/enemy.png",
+            enemy: "assets/level1/enemy_dirs.png",
             jumps: "assets/level1/jumpscare.png",
 
+            wall1_sheet: SheetLayout::STATIC,
+            wall2_sheet: SheetLayout::STATIC,
+            wall3_sheet: SheetLayout::STATIC,
+            wall4_sheet: SheetLayout::STATIC,
+            enemy_sheet: SheetLayout::directional(4, 8.0),
+
             music_game: "assets/level1/music_gameplay.ogg",
             music_jump: "assets/level1/music_jumpscare.ogg",
             music_go:   "assets/level1/music_gameover.ogg",
@@ -84,9 +165,44 @@ pub fn theme_for(level: u8) -> LevelTheme {
             enemy_loop: "assets/level1/enemy_loop.ogg",
 
             sky:   Color::new(150, 142, 59, 255),
+            sky_tex: None,
             floor: Color::new(133, 111, 27, 255),
 
-            lighting: Lighting { shade_min: 0.70, atten: 0.001 },
+            // Polvo amarillento suave en la distancia
+            lighting: Lighting {
+                shade_min: 0.70,
+                atten: 0.001,
+                fog: Some(Fog {
+                    color: Color::new(150, 142, 59, 255),
+                    curve: FogCurve::Linear { start: 3.0, end: 14.0 },
+                    max: 0.5,
+                    tint_sky: false,
+                    tint_minimap: 0.0,
+                }),
+            },
+
+            // Polvo cálido, un poco lavado
+            grade: ColorGrade {
+                lift: [0.03, 0.02, 0.0],
+                gamma: [1.0, 0.98, 0.92],
+                gain: [1.04, 1.0, 0.9],
+                saturation: 0.85,
+            },
+
+            // Tranquilo: una oleada tarde, sin refuerzos por llave
+            escalation: Escalation {
+                speed_per_min: 0.08,
+                speed_max: 1.3,
+                wave_after: 150.0,
+                wave_every: 120.0,
+                wave_size: 1,
+                spawn_per_key: 0,
+                max_enemies: 2,
+                spawn_min_cells: 7.0,
+                hunt_speed: 1.1,
+            },
+
+            key_mode: KeyMode::Master,
 
             img_logo:  "assets/ui/logo.png",
             img_card1: "assets/ui/card_level1.png",
@@ -105,9 +221,15 @@ pub fn theme_for(level: u8) -> LevelTheme {
             wall2: "assets/level2/wall2.png",
             wall3: "assets/level2/wall3.png",
             wall4: "assets/level2/wall4.png",
-            enemy: "assets/level2/enemy.png",
+            enemy: "assets/level2/enemy_dirs.png",
             jumps: "assets/level2/jumpscare.png",
 
+            wall1_sheet: SheetLayout::STATIC,
+            wall2_sheet: SheetLayout::STATIC,
+            wall3_sheet: SheetLayout::STATIC,
+            wall4_sheet: SheetLayout::STATIC,
+            enemy_sheet: SheetLayout::directional(4, 8.0),
+
             music_game: "assets/level2/music_gameplay.ogg",
             music_jump: "assets/level2/music_jumpscare.ogg",
             music_go:   "assets/level2/music_gameover.ogg",
@@ -115,9 +237,43 @@ pub fn theme_for(level: u8) -> LevelTheme {
             enemy_loop: "assets/level2/enemy_loop.ogg",
 
             sky:   Color::new(79, 79, 79, 255),
+            sky_tex: None,
             floor: Color::new(0, 128, 75, 255),
 
-            lighting: Lighting { shade_min: 0.65, atten: 0.0015 },
+            // Pantano: niebla verde-grisácea espesa
+            lighting: Lighting {
+                shade_min: 0.65,
+                atten: 0.0015,
+                fog: Some(Fog {
+                    color: Color::new(118, 132, 118, 255),
+                    curve: FogCurve::Exp2 { density: 0.22 },
+                    max: 0.92,
+                    tint_sky: true,
+                    tint_minimap: 0.25,
+                }),
+            },
+
+            // Pantano: verdes sucios, poca saturación
+            grade: ColorGrade {
+                lift: [0.0, 0.03, 0.01],
+                gamma: [0.95, 1.05, 0.95],
+                gain: [0.92, 1.02, 0.9],
+                saturation: 0.7,
+            },
+
+            escalation: Escalation {
+                speed_per_min: 0.12,
+                speed_max: 1.5,
+                wave_after: 100.0,
+                wave_every: 80.0,
+                wave_size: 1,
+                spawn_per_key: 1,
+                max_enemies: 6,
+                spawn_min_cells: 8.0,
+                hunt_speed: 1.2,
+            },
+
+            key_mode: KeyMode::Master,
 
             img_logo:  "assets/ui/logo.png",
             img_card1: "assets/ui/card_level1.png",
@@ -135,10 +291,16 @@ pub fn theme_for(level: u8) -> LevelTheme {
             wall1: "assets/level3/wall1.png",
             wall2: "assets/level3/wall2.png",
             wall3: "assets/level3/wall3.png",
-            wall4: "assets/level3/wall4.png",
-            enemy: "assets/level3/enemy.png",
+            wall4: "assets/level3/wall4_pulse.png", // 4 frames: venas que laten
+            enemy: "assets/level3/enemy_dirs.png",
             jumps: "assets/level3/jumpscare.png",
 
+            wall1_sheet: SheetLayout::STATIC,
+            wall2_sheet: SheetLayout::STATIC,
+            wall3_sheet: SheetLayout::STATIC,
+            wall4_sheet: SheetLayout::new(4, 1, 3.0),
+            enemy_sheet: SheetLayout::directional(4, 8.0),
+
             music_game: "assets/level3/music_gameplay.ogg",
             music_jump: "assets/level3/music_jumpscare.ogg",
             music_go:   "assets/level3/music_gameover.ogg",
@@ -146,9 +308,45 @@ pub fn theme_for(level: u8) -> LevelTheme {
             enemy_loop: "assets/level3/enemy_loop.ogg",
 
             sky:   Color::new(0, 40, 112, 255),
+            sky_tex: Some("assets/level3/sky.png"), // noche con luna
             floor: Color::new(143, 143, 143, 255),
 
-            lighting: Lighting { shade_min: 0.01, atten: 0.0070 },
+            // Noche azul: bruma fría que se traga lo lejano
+            lighting: Lighting {
+                shade_min: 0.01,
+                atten: 0.0070,
+                fog: Some(Fog {
+                    color: Color::new(12, 22, 48, 255),
+                    curve: FogCurve::Exp { density: 0.18 },
+                    max: 1.0,
+                    tint_sky: false,
+                    tint_minimap: 0.0,
+                }),
+            },
+
+            // Noche: sombras azuladas, luces frías
+            grade: ColorGrade {
+                lift: [0.0, 0.01, 0.05],
+                gamma: [0.95, 0.98, 1.08],
+                gain: [0.9, 0.95, 1.05],
+                saturation: 0.8,
+            },
+
+            // Enjambre: oleadas frecuentes y un refuerzo por llave
+            escalation: Escalation {
+                speed_per_min: 0.15,
+                speed_max: 1.7,
+                wave_after: 60.0,
+                wave_every: 45.0,
+                wave_size: 2,
+                spawn_per_key: 1,
+                max_enemies: 12,
+                spawn_min_cells: 9.0,
+                hunt_speed: 1.3,
+            },
+
+            // Cada puerta gasta una llave: hay que decidir cuáles abrir
+            key_mode: KeyMode::Consumed,
 
             img_logo:  "assets/ui/logo.png",
             img_card1: "assets/ui/card_level1.png",
@@ -14,6 +14,17 @@ mod gamemanager;
 mod hud;
 mod physics;
 mod audiomanager;
+mod flashlight;
+mod decals;
+mod postfx;
+mod fear;
+mod stealth;
+mod noise;
+mod archetype;
+mod flowfield;
+mod doors;
+mod items;
+mod registry;
 
 // NUEVOS
 mod appstate;
@@ -27,16 +38,29 @@ use raylib::core::audio::RaylibAudio;
 
 use audiomanager::{AudioManager, AudioConfig};
 
-use framebuffer::Framebuffer;
+use framebuffer::{Framebuffer, RenderScale, UpscaleFilter};
 use maze::{load_maze, find_char, maze_dims, Maze};
 use player::Player;
-use world3d::{render_world_textured, draw_overlay_fullscreen, draw_game_over_background, draw_win_background};
+use world3d::{render_world_textured, WorldBuffers, draw_overlay_fullscreen, draw_game_over_background, draw_win_background};
 use textures::TextureManager;
 use sprites::{collect_sprites, Sprite};
-use enemy::{Enemy, update_enemy};
-use gamemanager::{GameManager, GameState};
+use enemy::{Enemy, EnemyFrame, update_enemy};
+use archetype::Archetype;
+use flowfield::Flows;
+use doors::{Doors, DoorConfig, DoorEvent};
+use gamemanager::{Escalation, GameManager, GameState, GameEvent};
 use hud::Hud;
 use physics::resolve_player_collisions;
+use caster::{raycast, is_passable};
+use utils_grid::cell_center;
+use flashlight::{Flashlight, BATTERY_GLYPH};
+use decals::{DecalConfig, scatter_decals};
+use postfx::{PostFx, PostFxConfig};
+use fear::{Fear, FearConfig};
+use stealth::{Stealth, StealthConfig};
+use noise::{NoiseSystem, NoiseConfig};
+use items::{Items, ItemConfig, ItemEvent};
+use registry::{ItemId, KeyMode};
 
 use appstate::AppState;
 use save::{Progress, load_progress, save_progress};
@@ -44,18 +68,96 @@ use level::{LevelTheme, theme_for};
 use menu::{draw_start_screen, draw_level_select};
 use std::fs;
 
-fn recreate_enemies(cells: &[(i32,i32)], block_size: usize) -> Vec<Enemy> {
+fn recreate_enemies(cells: &[(i32,i32,Archetype)], block_size: usize) -> Vec<Enemy> {
     let mut v = Vec::with_capacity(cells.len());
-    for &(ci, cj) in cells { v.push(Enemy::from_cell(ci, cj, block_size)); }
+    for &(ci, cj, kind) in cells { v.push(Enemy::new(kind, ci, cj, block_size)); }
     v
 }
 
-fn forward_cell(pos: Vector2, ang: f32, block_size: usize, steps_px: f32) -> (i32,i32) {
-    let nx = pos.x + ang.cos() * steps_px;
-    let ny = pos.y + ang.sin() * steps_px;
-    let ci = (nx / block_size as f32).floor() as i32;
-    let cj = (ny / block_size as f32).floor() as i32;
-    (ci, cj)
+/// Recoge los spawns de enemigos (un glyph por arquetipo) y los borra del maze.
+fn take_enemy_spawns(maze: &mut Maze, out: &mut Vec<(i32,i32,Archetype)>) {
+    out.clear();
+    for (j,row) in maze.iter_mut().enumerate() {
+        for (i,c) in row.iter_mut().enumerate() {
+            if let Some(kind) = Archetype::from_glyph(*c) {
+                out.push((i as i32, j as i32, kind));
+                *c = ' ';
+            }
+        }
+    }
+}
+
+/// Lo que se saca del maze al (re)empezar un nivel: objetos del suelo
+/// (como sprites) y spawns de enemigos. Sus celdas quedan como pasillo.
+struct LevelObjects {
+    keys: Vec<Sprite>,
+    batteries: Vec<Sprite>,
+    items: Vec<Sprite>,
+    enemy_cells: Vec<(i32,i32,Archetype)>,
+}
+
+impl LevelObjects {
+    fn take_from(maze: &mut Maze, block_size: usize, tex: &TextureManager) -> Self {
+        let mut enemy_cells = Vec::new();
+        take_enemy_spawns(maze, &mut enemy_cells);
+
+        let keys = sprites::collect_keys(maze, block_size, tex);
+        let batteries = sprites::collect_batteries(maze, block_size, tex);
+        let items = sprites::collect_items(maze, block_size, tex);
+        for row in maze.iter_mut() {
+            for c in row.iter_mut() {
+                if *c == BATTERY_GLYPH || registry::by_glyph(*c).is_some() { *c = ' '; }
+            }
+        }
+        Self { keys, batteries, items, enemy_cells }
+    }
+}
+
+/// Oleada: hasta `count` enemigos nuevos (sin pasar de `esc.max_enemies`), a más de
+/// `esc.spawn_min_cells` del jugador.
+/// Prefieren los spawns del mapa; si están todos demasiado cerca, cualquier suelo lejano.
+/// El tipo sale de los que ya tiene el nivel.
+fn spawn_wave(
+    maze: &Maze,
+    enemies: &mut Vec<Enemy>,
+    spawns: &[(i32,i32,Archetype)],
+    player_cell: (i32,i32),
+    count: usize,
+    esc: &Escalation,
+    block_size: usize,
+) -> usize {
+    let (min_cells, max_total) = (esc.spawn_min_cells, esc.max_enemies);
+    use rand::Rng;
+    let mut rng = rand::rng();
+    let far = |c: (i32,i32)| {
+        let (dx, dy) = ((c.0 - player_cell.0) as f32, (c.1 - player_cell.1) as f32);
+        (dx * dx + dy * dy).sqrt() >= min_cells
+    };
+
+    let mut cells: Vec<(i32,i32)> = spawns.iter().map(|&(i, j, _)| (i, j)).filter(|&c| far(c)).collect();
+    if cells.is_empty() {
+        for (j, row) in maze.iter().enumerate() {
+            for (i, &c) in row.iter().enumerate() {
+                if is_passable(c) && far((i as i32, j as i32)) { cells.push((i as i32, j as i32)); }
+            }
+        }
+    }
+    if cells.is_empty() { return 0; }
+
+    let n = count.min(max_total.saturating_sub(enemies.len()));
+    for _ in 0..n {
+        let (ci, cj) = cells[rng.random_range(0..cells.len())];
+        let kind = if spawns.is_empty() { Archetype::Stalker } else { spawns[rng.random_range(0..spawns.len())].2 };
+        enemies.push(Enemy::new(kind, ci, cj, block_size));
+    }
+    n
+}
+
+/// Celda del primer bloque sólido (muro, puerta o reja) en la dirección de vista,
+/// si está a menos de `reach_px`.
+fn target_cell(maze: &Maze, pos: Vector2, ang: f32, block_size: usize, reach_px: f32) -> Option<(i32,i32)> {
+    let dir = Vector2::new(ang.cos(), ang.sin());
+    raycast(maze, pos, dir, reach_px, block_size, |c| !is_passable(c)).map(|hit| hit.cell)
 }
 
 fn level_path(level: u8) -> &'static str {
@@ -76,7 +178,7 @@ fn level_dims(level: u8) -> (usize, usize) {
 }
 
 /// Genera y persiste el maze del nivel y luego lo carga.
-fn load_maze_for_level(level: u8) -> Maze {
+fn load_maze_for_level(level: u8, key_mode: KeyMode) -> Maze {
     use crate::maze_gen::{make_maze_text_advanced, MazeGenConfig};
 
     let path = level_path(level);
@@ -84,9 +186,9 @@ fn load_maze_for_level(level: u8) -> Maze {
 
     // Puedes tunear por nivel si quieres variar
     let cfg = match level {
-        0 => MazeGenConfig { loop_factor: 0.12, donuts: 2, special_border_prob: 0.04, keys_per_type_base: 10, doors_per_type_base: 5, seed: None },
-        1 => MazeGenConfig { loop_factor: 0.16, donuts: 3, special_border_prob: 0.04, keys_per_type_base: 14, doors_per_type_base: 7, seed: None },
-        _ => MazeGenConfig { loop_factor: 0.22, donuts: 4, special_border_prob: 0.04, keys_per_type_base: 20, doors_per_type_base: 10, seed: None },
+        0 => MazeGenConfig { loop_factor: 0.12, donuts: 2, special_border_prob: 0.04, key_colors: &['1', '2', '3'], keys_per_type_base: 10, doors_per_type_base: 5, key_mode, spare_keys: 1, batteries_base: 4, flares_base: 2, lures_base: 2, wards_base: 1, see_through_base: 3, enemies: &['e'], seed: None },
+        1 => MazeGenConfig { loop_factor: 0.16, donuts: 3, special_border_prob: 0.04, key_colors: &['1', '2', '3', '4'], keys_per_type_base: 14, doors_per_type_base: 7, key_mode, spare_keys: 1, batteries_base: 5, flares_base: 2, lures_base: 3, wards_base: 2, see_through_base: 5, enemies: &['e', 'u', 'w'], seed: None },
+        _ => MazeGenConfig { loop_factor: 0.22, donuts: 4, special_border_prob: 0.04, key_colors: &['1', '2', '3', '4'], keys_per_type_base: 20, doors_per_type_base: 10, key_mode, spare_keys: 1, batteries_base: 6, flares_base: 3, lures_base: 3, wards_base: 2, see_through_base: 8, enemies: &['v', 'w', 't', 'u'], seed: None },
     };
 
     let txt = make_maze_text_advanced(w, h, cfg);
@@ -105,8 +207,7 @@ fn do_reset(
     time_s: &mut f32,
     hud: &mut Hud,
     maze_ref: &mut Maze,
-    keys_ref: &mut Vec<Sprite>,
-    enemy_cells_ref: &mut Vec<(i32,i32)>,
+    objects: &mut LevelObjects,
     audio_ref: &mut AudioManager,
     tex_manager: &TextureManager,
     maze_original: &Maze,
@@ -116,35 +217,16 @@ fn do_reset(
     player_spawn_fov: f32,
 ) {
     *maze_ref = maze_original.clone();
-
-    enemy_cells_ref.clear();
-    for (j,row) in maze_ref.iter().enumerate() {
-        for (i,&c) in row.iter().enumerate() {
-            if c == 'e' { enemy_cells_ref.push((i as i32, j as i32)); }
-        }
-    }
-    for row in maze_ref.iter_mut() {
-        for c in row.iter_mut() {
-            if *c == 'e' { *c = ' '; }
-        }
-    }
-
-    *keys_ref = sprites::collect_keys(maze_ref, block_size, tex_manager);
-    for row in maze_ref.iter_mut() {
-        for c in row.iter_mut() {
-            if *c == '1' || *c == '2' || *c == '3' { *c = ' '; }
-        }
-    }
+    *objects = LevelObjects::take_from(maze_ref, block_size, tex_manager);
 
     player.pos.x = player_spawn_px.0;
     player.pos.y = player_spawn_px.1;
     player.a     = player_spawn_angle;
     player.fov   = player_spawn_fov;
-    player.inv.key_yellow = false;
-    player.inv.key_blue   = false;
-    player.inv.key_red    = false;
+    player.inv = player::Inventory::default();
+    player.light = Flashlight::default();
 
-    *enemies = recreate_enemies(enemy_cells_ref, block_size);
+    *enemies = recreate_enemies(&objects.enemy_cells, block_size);
 
     *time_s = 0.0;
     gm.reset();
@@ -195,6 +277,11 @@ fn main() {
     let mut audio = AudioManager::new(&ra, audio_cfg);
 
     let mut framebuffer = Framebuffer::new(screen_w, screen_h, Color::BLACK);
+
+    // Resolución interna del 3D (F2: 100/50/25%, F3: nearest/bilinear). El HUD va nativo.
+    let mut render_scale = RenderScale { factor: 1.0, filter: UpscaleFilter::Nearest };
+    let mut scene_fb = Framebuffer::new(screen_w, screen_h, Color::BLACK);
+    let mut world_bufs = WorldBuffers::default(); // z-buffer y rejas, reutilizados entre frames
     let mut tex_manager = TextureManager::new(&mut rl, &thread);
 
     // Progreso y estado de app
@@ -207,8 +294,10 @@ fn main() {
     audio.load_theme_music(&current_theme);
 
     // Mapa inicial = nivel 1 (se reemplaza al elegir)
-    let mut maze_original: Maze = load_maze_for_level(0);
+    let mut maze_original: Maze = load_maze_for_level(0, current_theme.key_mode);
     let mut maze: Maze = maze_original.clone();
+    // Llaves que pide la salida (los colores que hay en el nivel)
+    let mut level_keys: Vec<ItemId> = registry::keys_in(&maze_original);
 
     let (mw, mh) = maze_dims(&maze);
     let block_size_x = (screen_w as usize / mw).max(1);
@@ -224,35 +313,18 @@ fn main() {
     let player_spawn_angle = std::f32::consts::PI / 3.0;
     let player_spawn_fov   = std::f32::consts::PI / 3.0;
 
-    // Enemigos desde mapa actual
-    let mut enemy_spawn_cells: Vec<(i32,i32)> = Vec::new();
-    for (j,row) in maze.iter().enumerate() {
-        for (i,&c) in row.iter().enumerate() {
-            if c == 'e' { enemy_spawn_cells.push((i as i32, j as i32)); }
-        }
-    }
-    for row in maze.iter_mut() {
-        for c in row.iter_mut() {
-            if *c == 'e' { *c = ' '; }
-        }
-    }
+    // Enemigos, llaves, pilas y consumibles desde el mapa actual (y limpiarlo)
+    let mut objects = LevelObjects::take_from(&mut maze, block_size, &tex_manager);
 
     let mut player = Player::new(
         Vector2::new(player_spawn_px.0, player_spawn_px.1),
         player_spawn_angle,
         player_spawn_fov,
     );
-    let mut enemies: Vec<Enemy> = recreate_enemies(&enemy_spawn_cells, block_size);
+    let mut enemies: Vec<Enemy> = recreate_enemies(&objects.enemy_cells, block_size);
 
     let sprites: Vec<Sprite> = collect_sprites(&maze, block_size, &tex_manager);
-
-    // Llaves desde mapa y limpiar
-    let mut keys_sprites = sprites::collect_keys(&maze, block_size, &tex_manager);
-    for row in maze.iter_mut() {
-        for c in row.iter_mut() {
-            if *c == '1' || *c == '2' || *c == '3' { *c = ' '; }
-        }
-    }
+    let mut decals = scatter_decals(&maze, &DecalConfig::default());
 
     let mut screen_tex = rl
         .load_texture_from_image(&thread, &framebuffer.color_buffer)
@@ -264,13 +336,26 @@ fn main() {
     // Game Manager
     let trigger_dist = (block_size as f32) * 0.85;
     let mut gm = GameManager::new(trigger_dist, 2.0);
+    gm.set_escalation(current_theme.escalation);
 
     // HUD
     let mut hud = Hud::new(&tex_manager);
     hud.apply_theme(&current_theme);
+    hud.level_keys = level_keys.clone();
+
+    // Post-procesado del viewport 3D (F4 lo activa/desactiva)
+    let mut postfx = PostFx::new(PostFxConfig::default());
+    postfx.apply_theme(&current_theme);
+    let mut fear = Fear::new(FearConfig::default());
+    let mut stealth = Stealth::new(StealthConfig::default());
+    let mut noise = NoiseSystem::new(NoiseConfig::default());
+    let mut flows = Flows::new();
+    let mut doors = Doors::new(DoorConfig::default());
+    let mut items = Items::new(ItemConfig::default());
 
     // Track de estado previo
     let mut prev_state = gm.state;
+    let mut jumpscare_tex = Archetype::Stalker.def().jumpscare;
 
     while !rl.window_should_close() {
         let dt = rl.get_frame_time();
@@ -345,29 +430,16 @@ fn main() {
                         tex_manager.apply_theme(&mut rl, &thread, &current_theme);
                         audio.load_theme_music(&current_theme);
                         hud.apply_theme(&current_theme);
+                        postfx.apply_theme(&current_theme);
 
-                        maze_original = load_maze_for_level(*selected);
+                        maze_original = load_maze_for_level(*selected, current_theme.key_mode);
                         maze = maze_original.clone();
+                        level_keys = registry::keys_in(&maze_original);
+                        hud.level_keys = level_keys.clone();
+                        decals = scatter_decals(&maze, &DecalConfig::default());
+                        // Spawns y objetos: los saca do_reset (más abajo) del maze nuevo
 
-                        // Recalcular spawns iniciales:
-                        enemy_spawn_cells.clear();
-                        for (j,row) in maze.iter().enumerate() {
-                            for (i,&c) in row.iter().enumerate() {
-                                if c == 'e' { enemy_spawn_cells.push((i as i32, j as i32)); }
-                            }
-                        }
-                        for row in maze.iter_mut() {
-                            for c in row.iter_mut() {
-                                if *c == 'e' { *c = ' '; }
-                            }
-                        }
-
-                        keys_sprites = sprites::collect_keys(&maze, block_size, &tex_manager);
-                        for row in maze.iter_mut() {
-                            for c in row.iter_mut() {
-                                if *c == '1' || *c == '2' || *c == '3' { *c = ' '; }
-                            }
-                        }
+                        gm.set_escalation(current_theme.escalation);
 
                         progress.last_level = *selected;
                         save_progress(&progress);
@@ -377,11 +449,17 @@ fn main() {
 
                         do_reset(
                             &mut player, &mut enemies, &mut gm, &mut time_s, &mut hud,
-                            &mut maze, &mut keys_sprites, &mut enemy_spawn_cells, &mut audio,
+                            &mut maze, &mut objects, &mut audio,
                             &tex_manager, &maze_original, block_size,
                             (player_spawn_px.0, player_spawn_px.1),
                             player_spawn_angle, player_spawn_fov,
                         );
+                        fear.reset();
+                        stealth.reset();
+                        noise.reset();
+                        flows.invalidate();
+                        doors.reset();
+                        items.reset();
                         prev_state = gm.state;
                     } else {
                         // opcional: SFX de error
@@ -394,6 +472,13 @@ fn main() {
                 // --- INPUTS previos a dibujo ---
                 let want_reset = (gm.is_game_over() || gm.is_win()) && rl.is_key_pressed(KeyboardKey::KEY_R);
                 let press_e    = rl.is_key_pressed(KeyboardKey::KEY_E);
+                let press_f    = rl.is_key_pressed(KeyboardKey::KEY_F);
+                let press_q    = rl.is_key_pressed(KeyboardKey::KEY_Q);
+                let press_tab  = rl.is_key_pressed(KeyboardKey::KEY_TAB);
+
+                if rl.is_key_pressed(KeyboardKey::KEY_F2) { render_scale.cycle_factor(); }
+                if rl.is_key_pressed(KeyboardKey::KEY_F3) { render_scale.toggle_filter(); }
+                if rl.is_key_pressed(KeyboardKey::KEY_F4) { postfx.toggle(); }
 
                 match gm.state {
                     GameState::Playing | GameState::JumpScare => rl.hide_cursor(),
@@ -401,27 +486,118 @@ fn main() {
                 }
 
                 if gm.is_playing() {
-                    crate::controller::process_events(&mut rl, &mut player, dt, screen_w, screen_h);
+                    let movement = crate::controller::process_events(&mut rl, &mut player, dt, screen_w, screen_h);
+
+                    // Linterna (F) + descarga de batería
+                    if press_f { player.light.toggle(); }
+                    player.light.update(dt);
 
                     let player_radius = (block_size as f32) * 0.20;
                     resolve_player_collisions(&mut player.pos, player_radius, &maze, block_size, 2);
 
-                    for e in &mut enemies { update_enemy(e, &maze, player.pos, block_size, dt); }
+                    // Sigilo: luz + movimiento (+ cercanía del enemigo más próximo para el HUD)
+                    let nearest = enemies.iter()
+                        .map(|e| (e.pos - player.pos).length() / block_size as f32)
+                        .fold(f32::INFINITY, f32::min);
+                    let ambient = current_theme.lighting.shade_min;
+                    stealth.update(dt, movement, player.light.intensity(time_s), ambient, nearest);
+
+                    // Correr hace ruido
+                    noise.footsteps(dt, movement, player.pos);
+
+                    // Un solo campo de flujo hacia el jugador para todos los que persiguen
+                    let pc = utils_grid::world_to_cell(player.pos.x, player.pos.y, block_size);
+                    flows.update(&maze, pc);
+
+                    // Posiciones de este frame para que se separen entre ellos
+                    let crowd: Vec<Vector2> = enemies.iter().map(|e| e.pos).collect();
+                    let speed_mult = gm.speed_mult();
+                    let frame = EnemyFrame {
+                        maze: &maze, player: &player, visibility: stealth.visibility,
+                        flows: &flows, crowd: &crowd, block_size, dt,
+                    };
+                    for (i, e) in enemies.iter_mut().enumerate() {
+                        e.speed_mult = speed_mult;
+                        e.hunting = gm.hunt;
+                        update_enemy(e, i, &frame);
+                        if e.teleported { audio.play_sfx("teleport", 0.8); }
+                    }
+
+                    // Enemigos forzando puertas: se abren, se rompen, y se oye
+                    for e in &enemies {
+                        let Some(cell) = e.door_push else { continue; };
+                        let Some(ev) = doors.work(&mut maze, &mut decals, cell, e.cfg.doors, dt) else { continue; };
+                        let at = cell_center(cell.0, cell.1, block_size);
+                        let vol = (1.0 - (at - player.pos).length() / (block_size as f32 * 8.0)).clamp(0.2, 1.0);
+                        match ev {
+                            DoorEvent::Opened => audio.play_sfx("door_open", 0.7 * vol),
+                            DoorEvent::Hit    => audio.play_sfx("door_bang", vol),
+                            DoorEvent::Broken => audio.play_sfx("door_break", vol),
+                        }
+                        noise.emit(at, noise.cfg.door);
+                        if ev != DoorEvent::Hit { flows.invalidate(); }
+                    }
+
+                    // Consumibles: TAB cambia de objeto, Q usa el seleccionado
+                    if press_tab { player.inv.cycle_consumable(); }
+                    let mut item_events = Vec::new();
+                    if press_q
+                        && let Some(id) = player.inv.selected
+                        && player.inv.has(id)
+                        && let Some(kind) = registry::def(id).effect
+                        && let Some(ev) = items.use_item(kind, &mut maze, &player, &mut enemies, block_size)
+                    {
+                        player.inv.take(id);
+                        item_events.push(ev);
+                    }
+                    item_events.extend(items.update(dt, &mut maze, &mut enemies, block_size));
+                    for ev in item_events {
+                        match ev {
+                            ItemEvent::FlareLit => audio.play_sfx("flare", 1.0),
+                            ItemEvent::LureThrown => {} // suena al caer
+                            ItemEvent::LureRing { pos } => {
+                                let vol = (1.0 - (pos - player.pos).length() / (block_size as f32 * 10.0)).clamp(0.2, 1.0);
+                                audio.play_sfx("lure", vol);
+                                noise.emit(pos, noise.cfg.thrown);
+                            }
+                            ItemEvent::WardUp { cell } | ItemEvent::WardDown { cell } => {
+                                let at = cell_center(cell.0, cell.1, block_size);
+                                let vol = (1.0 - (at - player.pos).length() / (block_size as f32 * 8.0)).clamp(0.2, 1.0);
+                                audio.play_sfx("ward", 0.8 * vol);
+                                flows.invalidate();
+                                // Los caminos cacheados que cruzan la celda se replanifican
+                                for e in enemies.iter_mut().filter(|e| e.path.contains(&cell)) {
+                                    e.path.clear();
+                                }
+                            }
+                        }
+                    }
                 }
 
-                let enemy_positions = enemies.iter().map(|e| e.pos);
+                // Los deslumbrados no pillan a nadie (ni al pasar a su lado)
+                let enemy_positions = enemies.iter().filter(|e| e.stunned <= 0.0).map(|e| e.pos);
                 gm.update(player.pos, enemy_positions, dt);
 
+                // El susto (imagen y SFX) es el del enemigo que te ha pillado
+                let scarer = enemies.iter()
+                    .filter(|e| e.stunned <= 0.0)
+                    .min_by(|a, b| (a.pos - player.pos).length().total_cmp(&(b.pos - player.pos).length()))
+                    .map_or(Archetype::Stalker, |e| e.kind);
+
                 // Audio 3D enemigo + mix
                 let max_hear = (block_size as f32) * 6.0;
                 audio.update_enemy_proximity(
                     player.pos,
                     player.a,
-                    enemies.iter().map(|e| e.pos),
+                    enemies.iter().map(|e| (e.pos, e.kind.def().loop_key)),
                     max_hear,
                 );
                 audio.update(dt);
 
+                // Miedo en pantalla: misma cercanía que el loop del enemigo
+                let near = if gm.is_playing() { audio.enemy_proximity() } else { 0.0 };
+                fear.update(dt, near);
+
                 // Cambio de estado → música/SFX
                 if gm.state != prev_state {
                     audio.on_state_changed(gm.state);
@@ -431,7 +607,8 @@ fn main() {
                         }
                         GameState::JumpScare => {
                             audio.switch_music("jumpscare", true);
-                            audio.play_sfx("jumpscare", 1.0);
+                            audio.play_sfx(scarer.def().sfx_jumpscare, 1.0);
+                            jumpscare_tex = scarer.def().jumpscare;
                         }
                         GameState::GameOver => {
                             audio.switch_music("gameover", true);
@@ -449,18 +626,47 @@ fn main() {
                 if gm.is_playing() {
                     let pick_radius = (block_size as f32) * 0.45;
                     let pick_r2 = pick_radius * pick_radius;
-                    keys_sprites.retain(|s| {
+                    // (si ya llevas todas las que caben de ese color, se queda en el suelo;
+                    // con llaves maestras basta una por color)
+                    let key_mode = current_theme.key_mode;
+                    objects.keys.retain(|s| {
                         let dx = s.pos.x - player.pos.x;
                         let dy = s.pos.y - player.pos.y;
                         let d2 = dx*dx + dy*dy;
-                        if d2 <= pick_r2 {
+                        if d2 <= pick_r2
+                            && let Some(id) = registry::by_glyph(s.tex)
+                            && (key_mode == KeyMode::Consumed || !player.inv.has(id))
+                        {
+                            let first = !player.inv.has(id);
+                            if !player.inv.add(id) { return true; }
                             audio.play_sfx("key_pick", 1.0);
-                            match s.tex {
-                                '1' => player.inv.key_yellow = true,
-                                '2' => player.inv.key_blue   = true,
-                                '3' => player.inv.key_red    = true,
-                                _ => {}
-                            }
+                            noise.emit(s.pos, noise.cfg.key_pick);
+                            // Refuerzos/caza solo al conseguir un color que no llevabas
+                            if first { gm.on_key_picked(player.inv.has_all(&level_keys)); }
+                            false
+                        } else { true }
+                    });
+
+                    // Pick-up pilas (recargan la linterna)
+                    objects.batteries.retain(|s| {
+                        let dx = s.pos.x - player.pos.x;
+                        let dy = s.pos.y - player.pos.y;
+                        if dx*dx + dy*dy <= pick_r2 {
+                            audio.play_sfx("key_pick", 0.7);
+                            player.light.recharge();
+                            false
+                        } else { true }
+                    });
+
+                    // Pick-up del resto de objetos (solo si caben en el inventario)
+                    objects.items.retain(|s| {
+                        let dx = s.pos.x - player.pos.x;
+                        let dy = s.pos.y - player.pos.y;
+                        if dx*dx + dy*dy <= pick_r2
+                            && let Some(id) = registry::by_glyph(s.tex)
+                            && player.inv.add(id)
+                        {
+                            audio.play_sfx("key_pick", 0.7);
                             false
                         } else { true }
                     });
@@ -468,76 +674,124 @@ fn main() {
 
                 // Interacción con puertas (E)
                 if press_e && gm.is_playing() {
-                    let (ci, cj) = forward_cell(player.pos, player.a, block_size, block_size as f32 * 0.6);
-                    if cj >= 0 && (cj as usize) < maze.len() && ci >= 0 && (ci as usize) < maze[cj as usize].len() {
+                    // Mismo alcance que antes (0.6 bloque), pero con rayo: no atraviesa rejas
+                    // y acierta la puerta aunque la mires en diagonal
+                    if let Some((ci, cj)) = target_cell(&maze, player.pos, player.a, block_size, block_size as f32 * 0.6)
+                        && cj >= 0 && (cj as usize) < maze.len() && ci >= 0 && (ci as usize) < maze[cj as usize].len() {
                         let cell = maze[cj as usize][ci as usize];
                         match cell {
-                            'Y' if player.inv.key_yellow => {
-                                maze[cj as usize][ci as usize] = ' ';
-                                audio.play_sfx("door_open", 0.9);
-                            }
-                            'B' if player.inv.key_blue   => {
-                                maze[cj as usize][ci as usize] = ' ';
-                                audio.play_sfx("door_open", 0.9);
-                            }
-                            'R' if player.inv.key_red    => {
-                                maze[cj as usize][ci as usize] = ' ';
-                                audio.play_sfx("door_open", 0.9);
-                            }
                             'G' => {
-                                if player.inv.has_all() {
+                                if player.inv.has_all(&level_keys) {
                                     gm.state = GameState::Win;
                                 }
                             }
+                            // Puertas de color: la llave que diga el registro
+                            c if registry::key_for_door(c).is_some_and(|k| player.inv.has(k)) => {
+                                // Llaves de un solo uso: la puerta se la queda
+                                if current_theme.key_mode == KeyMode::Consumed
+                                    && let Some(k) = registry::key_for_door(c)
+                                {
+                                    player.inv.take(k);
+                                }
+                                maze[cj as usize][ci as usize] = ' ';
+                                audio.play_sfx("door_open", 0.9);
+                                noise.emit(cell_center(ci, cj, block_size), noise.cfg.door);
+                                flows.invalidate();
+                                doors.forget((ci, cj));
+                            }
                             _ => {}
                         }
                     }
                 }
 
+                // Escalada: oleadas y caza final
+                for ev in gm.drain_events() {
+                    if !gm.is_playing() { break; }
+                    match ev {
+                        GameEvent::Spawn(n) => {
+                            let pc = utils_grid::world_to_cell(player.pos.x, player.pos.y, block_size);
+                            let added = spawn_wave(&maze, &mut enemies, &objects.enemy_cells, pc, n, &gm.escalation, block_size);
+                            if added > 0 { audio.play_sfx("teleport", 0.5); }
+                        }
+                        GameEvent::HuntStarted => {
+                            audio.play_sfx("hunt", 1.0);
+                            for e in enemies.iter_mut() { e.hunting = true; }
+                        }
+                    }
+                }
+
+                // Los ruidos del frame llegan a los enemigos que los oyen
+                if gm.is_playing() {
+                    noise.propagate(&maze, &mut enemies, block_size);
+                }
+
                 // Viewport 3D (excluye HUD)
                 let vp_y0 = 0;
                 let vp_h  = framebuffer.height - hud.height;
 
+                // Buffer interno del 3D a la resolución elegida
+                let (scene_w, scene_h) = render_scale.scaled(framebuffer.width, vp_h);
+                if scene_fb.width != scene_w || scene_fb.height != scene_h {
+                    scene_fb = Framebuffer::new(scene_w, scene_h, Color::BLACK);
+                }
+
+                // Consumibles del suelo + los que están en uso
+                let mut item_frame = objects.items.clone();
+                item_frame.extend(items.sprites(&tex_manager));
+
                 // --- RENDER ---
                 match gm.state {
                     GameState::Playing => {
                         render_world_textured(
-                            &mut framebuffer,
+                            &mut scene_fb,
                             &maze,
                             &player,
                             block_size,
                             &tex_manager,
                             &sprites,
                             &enemies,
-                            &keys_sprites,
+                            &objects.keys,
+                            &objects.batteries,
+                            &item_frame,
+                            &decals,
                             time_s,
-                            vp_y0,
-                            vp_h,
+                            0,
+                            scene_h,
                             &current_theme,
+                            &mut world_bufs,
                         );
-                        hud.render(&mut framebuffer, &tex_manager, &maze, &player, &enemies, &keys_sprites, block_size);
+                        let fb_w = framebuffer.width;
+                        framebuffer.blit_scaled(&scene_fb, 0, vp_y0, fb_w, vp_h, render_scale.filter);
+                        postfx.apply(&mut framebuffer, (0, vp_y0, fb_w, vp_h), time_s, &fear.frame(time_s));
+                        hud.render(&mut framebuffer, &tex_manager, &maze, &player, &enemies, &objects.keys, stealth.level, block_size);
                     }
                     GameState::JumpScare => {
                         render_world_textured(
-                            &mut framebuffer,
+                            &mut scene_fb,
                             &maze,
                             &player,
                             block_size,
                             &tex_manager,
                             &sprites,
                             &[],            // enemigos ocultos
-                            &keys_sprites,
+                            &objects.keys,
+                            &objects.batteries,
+                            &item_frame,
+                            &decals,
                             time_s,
-                            vp_y0,
-                            vp_h,
+                            0,
+                            scene_h,
                             &current_theme,
+                            &mut world_bufs,
                         );
                         let fb_w = framebuffer.width;
+                        framebuffer.blit_scaled(&scene_fb, 0, vp_y0, fb_w, vp_h, render_scale.filter);
+                        postfx.apply(&mut framebuffer, (0, vp_y0, fb_w, vp_h), time_s, &fear.frame(time_s));
                         world3d::draw_overlay_viewport(
-                            &mut framebuffer, &tex_manager, 'j',
+                            &mut framebuffer, &tex_manager, jumpscare_tex,
                             0, vp_y0, fb_w, vp_h
                         );
-                        hud.render(&mut framebuffer, &tex_manager, &maze, &player, &enemies, &keys_sprites, block_size);
+                        hud.render(&mut framebuffer, &tex_manager, &maze, &player, &enemies, &objects.keys, stealth.level, block_size);
                     }
 
                     GameState::GameOver => {
@@ -565,7 +819,19 @@ fn main() {
 
                     match gm.state {
                         GameState::Playing => {
-                            d.draw_text("E: Abrir puerta", 10, 10, 18, Color::RAYWHITE);
+                            let item = player.inv.selected
+                                .filter(|&id| player.inv.has(id))
+                                .map_or("nada", |id| registry::def(id).name);
+                            let help = format!("E: Abrir puerta  |  F: Linterna  |  Q: Usar ({item})  |  TAB: Cambiar");
+                            d.draw_text(&help, 10, 10, 18, Color::RAYWHITE);
+                            if gm.hunt {
+                                // Parpadea mientras dura la caza
+                                let text = "¡VIENEN A POR TI!  Corre a la salida";
+                                let fs = 28;
+                                let tw = d.measure_text(text, fs);
+                                let a = (0.6 + 0.4 * (time_s * 6.0).sin()).clamp(0.0, 1.0);
+                                d.draw_text(text, (screen_w - tw) / 2, 44, fs, Color::new(230, 41, 55, (a * 255.0) as u8));
+                            }
                         }
                         GameState::JumpScare => { /* overlay tapa */ }
                         GameState::GameOver => {
@@ -604,11 +870,18 @@ fn main() {
                 if want_reset {
                     do_reset(
                         &mut player, &mut enemies, &mut gm, &mut time_s, &mut hud,
-                        &mut maze, &mut keys_sprites, &mut enemy_spawn_cells, &mut audio,
+                        &mut maze, &mut objects, &mut audio,
                         &tex_manager, &maze_original, block_size,
                         (player_spawn_px.0, player_spawn_px.1),
                         player_spawn_angle, player_spawn_fov,
                     );
+                    fear.reset();
+                    stealth.reset();
+                    noise.reset();
+                    flows.invalidate();
+                    doors.reset();
+                    items.reset();
+                    decals = scatter_decals(&maze, &DecalConfig::default());
                 } else if go_menu {
                     if gm.is_win() {
                         if (level as usize) < 2 && !progress.unlocked[level as usize + 1] {
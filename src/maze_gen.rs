@@ -1,6 +1,12 @@
 use rand::{seq::SliceRandom, Rng, SeedableRng, rngs::StdRng};
 use std::cmp::Ordering;
 
+use crate::flashlight::BATTERY_GLYPH;
+use crate::items::{FLARE_GLYPH, LURE_GLYPH, WARD_GLYPH};
+use crate::registry::{self, KeyMode};
+use crate::caster::is_passable;
+use crate::archetype::Archetype;
+
 /// Config de generación (valores por defecto razonables para “donas” y variedad)
 #[derive(Clone, Copy)]
 pub struct MazeGenConfig {
@@ -10,10 +16,26 @@ pub struct MazeGenConfig {
     pub donuts: usize,
     /// Probabilidad de colocar pared especial en el borde superior/derecho (0..1, pequeña)
     pub special_border_prob: f32,
+    /// Colores de llave del nivel, por glyph de llave (ver registry.rs); cada uno trae sus puertas
+    pub key_colors: &'static [char],
     /// Cantidad base de llaves por tipo (escala con el área)
     pub keys_per_type_base: usize,
     /// Cantidad base de puertas por tipo (se colocan en pasillos, bloqueando)
     pub doors_per_type_base: usize,
+    /// Master: llaves según `keys_per_type_base`. Consumed: una llave por puerta
+    /// colocada + `spare_keys` (la de la salida y algo de margen)
+    pub key_mode: KeyMode,
+    pub spare_keys: usize,
+    /// Cantidad base de pilas para la linterna (escala con el área)
+    pub batteries_base: usize,
+    /// Cantidad base de consumibles (bengalas, cascabeles, amuletos), escala con el área
+    pub flares_base: usize,
+    pub lures_base: usize,
+    pub wards_base: usize,
+    /// Cantidad base de muros interiores que pasan a ser rejas/alambradas/ventanas rotas
+    pub see_through_base: usize,
+    /// Enemigos del nivel, por glyph de arquetipo (ver archetype.rs)
+    pub enemies: &'static [char],
     /// Usar semilla fija (opcional)
     pub seed: Option<u64>,
 }
@@ -24,8 +46,17 @@ impl Default for MazeGenConfig {
             loop_factor: 0.15,         // abre ~15% aristas extra para loops
             donuts: 6,                 // 6 “donas” por mapa grande
             special_border_prob: 0.04, // 4% en paredes top/right
+            key_colors: &['1', '2', '3'], // amarilla, azul, roja
             keys_per_type_base: 4,     // se escala con el tamaño
             doors_per_type_base: 3,    // se escala con el tamaño
+            key_mode: KeyMode::Master, // una llave abre todas las de su color
+            spare_keys: 1,             // solo cuenta en KeyMode::Consumed
+            batteries_base: 3,         // se escala con el tamaño
+            flares_base: 1,
+            lures_base: 2,
+            wards_base: 1,
+            see_through_base: 4,       // se escala con el tamaño
+            enemies: &['e'],           // un stalker
             seed: None,
         }
     }
@@ -217,21 +248,26 @@ pub fn make_maze_text_advanced(w: usize, h: usize, cfg: MazeGenConfig) -> String
         .unwrap_or((W.saturating_sub(2), H.saturating_sub(2)));
     grid[far_g.1.min(H-1)][far_g.0.min(W-1)] = 'G';
 
-    // Enemigo e: el más alejado de p que no sea p ni G
-    let mut floors2: Vec<(usize, usize)> = floors
+    // Enemigos: lo más lejos posible de p (sin pisar p ni G) y separados entre sí
+    let mut by_far: Vec<(usize, usize)> = floors
         .into_iter()
         .filter(|&(i, j)| (i, j) != (px, py) && (i, j) != far_g)
         .collect();
-    let far_e = floors2
-        .iter()
-        .max_by_key(|(ix, iy)| {
-            let dx = *ix as isize - px as isize;
-            let dy = *iy as isize - py as isize;
-            (dx*dx + dy*dy) as i64
-        })
-        .copied()
-        .unwrap_or((W/2, H/2));
-    grid[far_e.1.min(H-1)][far_e.0.min(W-1)] = 'e';
+    let d2p = |&(ix, iy): &(usize, usize)| {
+        let dx = ix as isize - px as isize;
+        let dy = iy as isize - py as isize;
+        (dx*dx + dy*dy) as i64
+    };
+    by_far.sort_by_key(|c| std::cmp::Reverse(d2p(c)));
+    let mut placed: Vec<(usize, usize)> = Vec::new();
+    for &glyph in cfg.enemies {
+        let spacing = |min: usize| by_far.iter().copied().find(|&(i, j)| {
+            !placed.contains(&(i, j)) && placed.iter().all(|&(a, b)| i.abs_diff(a) + j.abs_diff(b) >= min)
+        });
+        let at = spacing(8).or_else(|| spacing(3)).or_else(|| spacing(0)).unwrap_or((W/2, H/2));
+        placed.push(at);
+        grid[at.1.min(H-1)][at.0.min(W-1)] = glyph;
+    }
 
 
     // ---------------------------
@@ -248,24 +284,66 @@ pub fn make_maze_text_advanced(w: usize, h: usize, cfg: MazeGenConfig) -> String
         }
     }
 
-    // ---------------------------
-    // 7) Llaves y Puertas (múltiples por tipo)
-    // ---------------------------
     // Escalado por tamaño del mapa ASCII (no por w,h de celdas)
     let area = (W * H).max(1) as f32;
     let scale = (area / 12_000.0).clamp(0.6, 2.2); // ajusta densidad para mapas grandes
+
+    // ---------------------------
+    // 6b) Rejas / alambradas / ventanas rotas (se ve a través, no se pasa)
+    // ---------------------------
+    let see_through = ((cfg.see_through_base as f32) * scale).round() as usize;
+    place_see_through(&mut grid, see_through, &mut rng);
+
+    // ---------------------------
+    // 7) Llaves y Puertas (múltiples por tipo)
+    // ---------------------------
     let keys_per_type  = ((cfg.keys_per_type_base as f32)  * scale).round() as usize;
     let doors_per_type = ((cfg.doors_per_type_base as f32) * scale).round() as usize;
 
-    place_multiple(&mut grid, '1', keys_per_type, &mut rng); // amarilla
-    place_multiple(&mut grid, '2', keys_per_type, &mut rng); // azul
-    place_multiple(&mut grid, '3', keys_per_type, &mut rng); // roja
+    // Solo los glyphs que el registro conoce como llave con puerta
+    let key_doors: Vec<(char, char)> = cfg.key_colors.iter()
+        .filter_map(|&k| registry::by_glyph(k))
+        .filter_map(|id| registry::def(id).door.as_ref().map(|d| (registry::def(id).glyph, d.glyph)))
+        .collect();
+
+    if cfg.key_mode == KeyMode::Master {
+        for &(key, _) in &key_doors {
+            place_multiple(&mut grid, key, keys_per_type, &mut rng);
+        }
+    }
+
+    let batteries = ((cfg.batteries_base as f32) * scale).round() as usize;
+    place_multiple(&mut grid, BATTERY_GLYPH, batteries, &mut rng); // pilas linterna
+
+    let per_area = |base: usize| ((base as f32) * scale).round() as usize;
+    place_multiple(&mut grid, FLARE_GLYPH, per_area(cfg.flares_base), &mut rng); // bengalas
+    place_multiple(&mut grid, LURE_GLYPH,  per_area(cfg.lures_base),  &mut rng); // cascabeles
+    place_multiple(&mut grid, WARD_GLYPH,  per_area(cfg.wards_base),  &mut rng); // amuletos
 
     // Las puertas son celdas sólidas que se colocan en corredores (reemplazan un ' ')
     // Evitamos colocarlas a 4 celdas de p para no bloquear el spawn inmediato.
-    place_doors(&mut grid, 'Y', doors_per_type, (px, py), 4, &mut rng);
-    place_doors(&mut grid, 'B', doors_per_type, (px, py), 4, &mut rng);
-    place_doors(&mut grid, 'R', doors_per_type, (px, py), 4, &mut rng);
+    let mut doors_placed: Vec<(char, usize)> = Vec::with_capacity(key_doors.len());
+    for &(key, door) in &key_doors {
+        let n = place_doors(&mut grid, door, doors_per_type, (px, py), 4, &mut rng);
+        doors_placed.push((key, n));
+    }
+
+    // Llaves que se gastan: se colocan después, cuadrando con las puertas que
+    // salieron de verdad. Una de cada color sin cruzar ninguna puerta desde p,
+    // para que siempre se pueda empezar.
+    if cfg.key_mode == KeyMode::Consumed {
+        let mut start = reachable_floor(&grid, (px, py));
+        start.shuffle(&mut rng);
+        for &(key, doors) in &doors_placed {
+            let mut left = doors + cfg.spare_keys;
+            if left > 0 && let Some(k) = start.iter().position(|&(x, y)| grid[y][x] == ' ') {
+                let (x, y) = start.swap_remove(k);
+                grid[y][x] = key;
+                left -= 1;
+            }
+            place_multiple(&mut grid, key, left, &mut rng);
+        }
+    }
 
     // ---------------------------
     // 8) Reconstruir string final
@@ -325,8 +403,59 @@ fn place_multiple(grid: &mut [Vec<char>], ch: char, count: usize, rng: &mut impl
     }
 }
 
+/// Cambia muros interiores entre dos pasillos por bloques transparentes:
+/// '|' con hueco a izquierda y derecha, o '-' con hueco arriba y abajo.
+fn place_see_through(grid: &mut [Vec<char>], count: usize, rng: &mut impl Rng) {
+    let H = grid.len();
+    let W = grid[0].len();
+
+    let mut candidates: Vec<(usize, usize)> = Vec::new();
+    for j in 1..H.saturating_sub(1) {
+        for i in 1..W.saturating_sub(1) {
+            let open = |c: char| c == ' ';
+            let ok = match grid[j][i] {
+                '|' => open(grid[j][i - 1]) && open(grid[j][i + 1]),
+                '-' => open(grid[j - 1][i]) && open(grid[j + 1][i]),
+                _ => false,
+            };
+            if ok { candidates.push((i, j)); }
+        }
+    }
+
+    candidates.shuffle(rng);
+    for (x, y) in candidates.into_iter().take(count) {
+        grid[y][x] = match rng.gen_range(0..3) {
+            0 => '=', // reja
+            1 => ':', // alambrada
+            _ => '/', // ventana rota
+        };
+    }
+}
+
+/// Celdas libres (' ') a las que se llega desde `from` sin cruzar muros ni puertas.
+fn reachable_floor(grid: &[Vec<char>], from: (usize, usize)) -> Vec<(usize, usize)> {
+    let H = grid.len();
+    let W = grid[0].len();
+    let mut seen = vec![vec![false; W]; H];
+    let mut stack = vec![from];
+    let mut out = Vec::new();
+    seen[from.1][from.0] = true;
+    while let Some((x, y)) = stack.pop() {
+        if grid[y][x] == ' ' { out.push((x, y)); }
+        for (dx, dy) in [(1isize, 0isize), (-1, 0), (0, 1), (0, -1)] {
+            let (nx, ny) = (x as isize + dx, y as isize + dy);
+            if nx < 0 || ny < 0 || nx as usize >= W || ny as usize >= H { continue; }
+            let (nx, ny) = (nx as usize, ny as usize);
+            if seen[ny][nx] || !is_passable(grid[ny][nx]) { continue; }
+            seen[ny][nx] = true;
+            stack.push((nx, ny));
+        }
+    }
+    out
+}
+
 /// Puertas sólidas dentro de pasillos; tratamos de no bloquear el spawn inmediato del jugador.
-/// Distancia mínima en Manhattan desde `avoid` (usualmente p).
+/// Distancia mínima en Manhattan desde `avoid` (usualmente p). Devuelve cuántas colocó.
 fn place_doors(
     grid: &mut [Vec<char>],
     door_ch: char,
@@ -334,7 +463,7 @@ fn place_doors(
     avoid: (usize, usize),
     min_manhattan: usize,
     rng: &mut impl Rng
-) {
+) -> usize {
     let H = grid.len();
     let W = grid[0].len();
 
@@ -357,7 +486,7 @@ fn place_doors(
             let down  = nb(i as isize, j as isize + 1);
 
             let is_solid = |c: char| matches!(c, '+' | '-' | '|' | '#' | '@' | '!' );
-            let is_free  = |c: char| c == ' ' || c == '1' || c == '2' || c == '3';
+            let is_free  = |c: char| c == ' ' || c == BATTERY_GLYPH || registry::by_glyph(c).is_some();
 
             // Cuenta simples
             for c in [left, right, up, down] {
@@ -375,19 +504,23 @@ fn place_doors(
             if md < min_manhattan { continue; }
 
             // No colocar sobre G, p, e
-            if matches!(nb(i as isize, j as isize), 'p' | 'e' | 'G') { continue; }
+            let c = nb(i as isize, j as isize);
+            if matches!(c, 'p' | 'G') || Archetype::from_glyph(c).is_some() { continue; }
 
             candidates.push((i, j));
         }
     }
 
     candidates.shuffle(rng);
+    let mut placed = 0;
     for (k, (x, y)) in candidates.into_iter().enumerate() {
         if k >= count { break; }
         if grid[y][x] == ' ' {
             grid[y][x] = door_ch;
+            placed += 1;
         }
     }
+    placed
 }
 
 /// Versión compatible con tu firma anterior (mantiene comportamiento “sano” por defecto)
@@ -0,0 +1,136 @@
+// noise.rs
+// Ruido del juego: cada evento (correr, puertas, llaves, objetos lanzados)
+// emite un ruido con un volumen en celdas. Se propaga por las celdas
+// caminables (BFS, distancia de camino), así que los muros lo amortiguan.
+
+use std::collections::VecDeque;
+use raylib::prelude::Vector2;
+use crate::caster::is_passable;
+use crate::controller::Movement;
+use crate::enemy::Enemy;
+use crate::maze::Maze;
+use crate::utils_grid::world_to_cell;
+
+/// Volúmenes (alcance en celdas de camino) por tipo de evento.
+#[derive(Clone, Copy)]
+pub struct NoiseConfig {
+    pub sprint: f32,          // cada pisada corriendo
+    pub sprint_interval: f32, // seg entre pisadas que se oyen
+    pub door: f32,
+    pub key_pick: f32,
+    pub thrown: f32,
+}
+
+impl Default for NoiseConfig {
+    fn default() -> Self {
+        Self {
+            sprint: 6.0,
+            sprint_interval: 0.35,
+            door: 8.0,
+            key_pick: 3.0,
+            thrown: 10.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Noise {
+    pub pos: Vector2,  // px
+    pub loudness: f32, // alcance en celdas
+}
+
+pub struct NoiseSystem {
+    pub cfg: NoiseConfig,
+    pending: Vec<Noise>,
+    step_accum: f32,
+    dist: Vec<i32>, // distancias del BFS (reutilizado entre ruidos)
+}
+
+impl NoiseSystem {
+    pub fn new(cfg: NoiseConfig) -> Self {
+        Self { cfg, pending: Vec::new(), step_accum: 0.0, dist: Vec::new() }
+    }
+
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.step_accum = 0.0;
+    }
+
+    pub fn emit(&mut self, pos: Vector2, loudness: f32) {
+        if loudness > 0.0 { self.pending.push(Noise { pos, loudness }); }
+    }
+
+    /// Pisadas: corriendo hace ruido cada `sprint_interval` segundos.
+    pub fn footsteps(&mut self, dt: f32, movement: Movement, pos: Vector2) {
+        if movement != Movement::Sprint {
+            self.step_accum = 0.0;
+            return;
+        }
+        self.step_accum += dt;
+        if self.step_accum >= self.cfg.sprint_interval {
+            self.step_accum -= self.cfg.sprint_interval;
+            self.emit(pos, self.cfg.sprint);
+        }
+    }
+
+    /// Propaga los ruidos pendientes y avisa a los enemigos que los oyen.
+    pub fn propagate(&mut self, maze: &Maze, enemies: &mut [Enemy], block_size: usize) {
+        for n in std::mem::take(&mut self.pending) {
+            let origin = world_to_cell(n.pos.x, n.pos.y, block_size);
+            // Algunos enemigos oyen más lejos: se inunda hasta el mejor oído
+            let best = enemies.iter().map(|e| e.cfg.hearing).fold(1.0, f32::max);
+            self.flood(maze, origin, (n.loudness * best).floor() as i32);
+
+            let w = maze.first().map_or(0, |r| r.len()) as i32;
+            for e in enemies.iter_mut() {
+                let (ci, cj) = world_to_cell(e.pos.x, e.pos.y, block_size);
+                if ci < 0 || cj < 0 || ci >= w || cj as usize >= maze.len() { continue; }
+                let d = self.dist[(cj * w + ci) as usize];
+                if d >= 0 && d <= (n.loudness * e.cfg.hearing).floor() as i32 { e.hear(maze, origin, block_size); }
+            }
+        }
+    }
+
+    /// BFS por celdas caminables desde `origin` hasta `reach` pasos.
+    /// Si el ruido nace en una celda sólida (una puerta), sale por sus vecinas.
+    fn flood(&mut self, maze: &Maze, origin: (i32,i32), reach: i32) {
+        let h = maze.len() as i32;
+        let w = maze.first().map_or(0, |r| r.len()) as i32;
+        self.dist.clear();
+        self.dist.resize((w * h).max(0) as usize, -1);
+
+        let open = |i: i32, j: i32| -> bool {
+            i >= 0 && j >= 0 && j < h && (i as usize) < maze[j as usize].len()
+                && is_passable(maze[j as usize][i as usize])
+        };
+        const OFFS: [(i32,i32);4] = [(1,0),(-1,0),(0,1),(0,-1)];
+
+        let mut q = VecDeque::new();
+        if open(origin.0, origin.1) {
+            self.dist[(origin.1 * w + origin.0) as usize] = 0;
+            q.push_back(origin);
+        } else {
+            for (dx, dy) in OFFS {
+                let (i, j) = (origin.0 + dx, origin.1 + dy);
+                if i < w && open(i, j) {
+                    self.dist[(j * w + i) as usize] = 1;
+                    q.push_back((i, j));
+                }
+            }
+        }
+
+        while let Some((i, j)) = q.pop_front() {
+            let d = self.dist[(j * w + i) as usize];
+            if d >= reach { continue; }
+            for (dx, dy) in OFFS {
+                let (ni, nj) = (i + dx, j + dy);
+                if ni >= w || !open(ni, nj) { continue; }
+                let k = (nj * w + ni) as usize;
+                if self.dist[k] < 0 {
+                    self.dist[k] = d + 1;
+                    q.push_back((ni, nj));
+                }
+            }
+        }
+    }
+}
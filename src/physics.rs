@@ -1,11 +1,12 @@
 // physics.rs
 use raylib::prelude::Vector2;
 use crate::maze::Maze;
+use crate::caster::is_passable;
 
-/// ¿Es sólida la celda? (mismo set que tu DDA)
+/// ¿Es sólida la celda? (mismo set que tu DDA, rejas incluidas)
 #[inline]
 fn is_solid(c: char) -> bool {
-    matches!(c, '+' | '-' | '|' | '#' | 'g' | 'Y' | 'B' | 'R' | 'G' | '@' | '!')
+    !is_passable(c)
 }
 
 /// Empuja un punto (jugador) fuera del AABB de una celda sólida.
@@ -58,7 +59,7 @@ fn push_out_of_cell(
     }
 }
 
-/// Empuja al jugador fuera de cualquier pared alrededor.
+/// Empuja al jugador (o a un enemigo: cualquier círculo) fuera de cualquier pared alrededor.
 /// `iterations` ayuda a resolver esquinas en múltiples pasos.
 pub fn resolve_player_collisions(
     pos: &mut Vector2,
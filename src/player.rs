@@ -1,16 +1,71 @@
 use raylib::prelude::Vector2;
+use crate::flashlight::Flashlight;
+use crate::registry::{self, Category, ItemId, ITEM_DEFS};
 
-#[derive(Default, Clone, Copy)]
+/// Lo que lleva el jugador: una cantidad por entrada del registro.
+#[derive(Clone)]
 pub struct Inventory {
-    pub key_yellow: bool,
-    pub key_blue:   bool,
-    pub key_red:    bool,
+    counts: Vec<u8>,
+    /// Consumible que se usa con Q
+    pub selected: Option<ItemId>,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self { counts: vec![0; ITEM_DEFS.len()], selected: None }
+    }
 }
 
 impl Inventory {
     #[inline]
-    pub fn has_all(&self) -> bool {
-        self.key_yellow && self.key_blue && self.key_red
+    pub fn count(&self, id: ItemId) -> u8 {
+        self.counts[id.0 as usize]
+    }
+
+    #[inline]
+    pub fn has(&self, id: ItemId) -> bool {
+        self.count(id) > 0
+    }
+
+    /// ¿Lleva una de cada llave de la lista? (las del nivel, ver `registry::keys_in`)
+    pub fn has_all(&self, keys: &[ItemId]) -> bool {
+        keys.iter().all(|&k| self.has(k))
+    }
+
+    /// Guarda uno si cabe (límite de pila del registro).
+    pub fn add(&mut self, id: ItemId) -> bool {
+        let n = &mut self.counts[id.0 as usize];
+        if *n >= registry::def(id).stack { return false; }
+        *n += 1;
+        // Si no había nada seleccionado, lo recién cogido
+        if registry::def(id).category == Category::Consumable
+            && self.selected.is_none_or(|s| self.counts[s.0 as usize] == 0)
+        {
+            self.selected = Some(id);
+        }
+        true
+    }
+
+    pub fn take(&mut self, id: ItemId) -> bool {
+        let n = &mut self.counts[id.0 as usize];
+        if *n == 0 { return false; }
+        *n -= 1;
+        true
+    }
+
+    /// Lo que lleva (cantidad > 0), en el orden del registro.
+    pub fn held(&self) -> impl Iterator<Item = (ItemId, u8)> + '_ {
+        registry::ids().map(|id| (id, self.count(id))).filter(|&(_, n)| n > 0)
+    }
+
+    /// Siguiente consumible que tenga algo (en el orden del registro).
+    pub fn cycle_consumable(&mut self) {
+        let owned: Vec<ItemId> = registry::of_category(Category::Consumable).filter(|&id| self.has(id)).collect();
+        if owned.is_empty() { return; }
+        let next = self.selected
+            .and_then(|s| owned.iter().position(|&id| id == s))
+            .map_or(0, |i| (i + 1) % owned.len());
+        self.selected = Some(owned[next]);
     }
 }
 
@@ -19,10 +74,11 @@ pub struct Player {
     pub a: f32,       // ángulo de vista (radianes)
     pub fov: f32,     // field of view (radianes)
     pub inv: Inventory,
+    pub light: Flashlight, // linterna + batería
 }
 
 impl Player {
     pub fn new(pos: Vector2, a: f32, fov: f32) -> Self {
-        Self { pos, a, fov, inv: Inventory::default() }
+        Self { pos, a, fov, inv: Inventory::default(), light: Flashlight::default() }
     }
 }
@@ -0,0 +1,267 @@
+// postfx.rs
+// Post-procesado en CPU sobre el framebuffer, después del 3D y antes del HUD:
+// aberración cromática, grading por tema (LUT), viñeta, scanlines y grano.
+
+use crate::framebuffer::{Framebuffer, StripView, render_threads};
+use crate::level::LevelTheme;
+use crate::fear::FearFrame;
+
+/// Grading de color por tema (lift/gamma/gain por canal + saturación).
+/// Se hornea a una LUT de 256 entradas por canal.
+#[derive(Clone, Copy)]
+pub struct ColorGrade {
+    pub lift: [f32; 3],  // suma en sombras (-0.2..0.2)
+    pub gamma: [f32; 3], // >1 aclara medios tonos
+    pub gain: [f32; 3],  // multiplicador en luces
+    pub saturation: f32, // 1 = igual, 0 = gris
+}
+
+impl ColorGrade {
+    pub const NEUTRAL: ColorGrade = ColorGrade {
+        lift: [0.0; 3],
+        gamma: [1.0; 3],
+        gain: [1.0; 3],
+        saturation: 1.0,
+    };
+}
+
+#[derive(Clone, Copy)]
+pub struct VignetteConfig {
+    pub enabled: bool,
+    pub strength: f32, // oscurecimiento en las esquinas (0..1)
+    pub radius: f32,   // distancia (0 centro .. 1 esquina) donde termina el oscurecimiento
+    pub softness: f32, // ancho del degradado
+}
+
+#[derive(Clone, Copy)]
+pub struct GrainConfig {
+    pub enabled: bool,
+    pub amount: f32, // amplitud del ruido (0..1)
+    pub fps: f32,    // cuántas veces por segundo cambia el patrón
+}
+
+#[derive(Clone, Copy)]
+pub struct ChromaConfig {
+    pub enabled: bool,
+    pub offset_px: f32, // separación R/B en los bordes laterales (px)
+}
+
+#[derive(Clone, Copy)]
+pub struct ScanlineConfig {
+    pub enabled: bool,
+    pub period: i32,    // cada cuántas filas hay una línea oscura
+    pub darkness: f32,  // cuánto oscurece la línea (0..1)
+}
+
+#[derive(Clone, Copy)]
+pub struct PostFxConfig {
+    pub enabled: bool,
+    pub grade: bool, // usar la LUT del tema
+    pub vignette: VignetteConfig,
+    pub grain: GrainConfig,
+    pub chroma: ChromaConfig,
+    pub scanlines: ScanlineConfig,
+}
+
+impl Default for PostFxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            grade: true,
+            vignette:  VignetteConfig { enabled: true, strength: 0.55, radius: 1.0, softness: 0.6 },
+            grain:     GrainConfig { enabled: true, amount: 0.05, fps: 24.0 },
+            chroma:    ChromaConfig { enabled: true, offset_px: 1.5 },
+            scanlines: ScanlineConfig { enabled: false, period: 3, darkness: 0.15 },
+        }
+    }
+}
+
+pub struct PostFx {
+    pub cfg: PostFxConfig,
+    lut: [[u8; 256]; 3],
+    saturation: f32,
+    src: Vec<u8>, // copia del framebuffer (la aberración lee píxeles vecinos)
+}
+
+impl PostFx {
+    pub fn new(cfg: PostFxConfig) -> Self {
+        let mut fx = Self { cfg, lut: [[0; 256]; 3], saturation: 1.0, src: Vec::new() };
+        fx.set_grade(&ColorGrade::NEUTRAL);
+        fx
+    }
+
+    pub fn apply_theme(&mut self, theme: &LevelTheme) {
+        self.set_grade(&theme.grade);
+    }
+
+    pub fn toggle(&mut self) {
+        self.cfg.enabled = !self.cfg.enabled;
+    }
+
+    /// Hornea el grading a la LUT por canal.
+    pub fn set_grade(&mut self, g: &ColorGrade) {
+        for c in 0..3 {
+            for i in 0..256 {
+                let x = i as f32 / 255.0;
+                let v = (x * g.gain[c] + g.lift[c] * (1.0 - x)).clamp(0.0, 1.0);
+                let v = v.powf(1.0 / g.gamma[c].max(0.01));
+                self.lut[c][i] = (v * 255.0).round() as u8;
+            }
+        }
+        self.saturation = g.saturation;
+    }
+
+    /// Aplica la cadena sobre el rectángulo (x,y,w,h) del framebuffer.
+    /// Los efectos de miedo se aplican aunque el post-procesado esté apagado.
+    pub fn apply(&mut self, fb: &mut Framebuffer, rect: (i32, i32, i32, i32), time_s: f32, fear: &FearFrame) {
+        let (x, y, w, h) = rect;
+        if (!self.cfg.enabled && fear.is_idle()) || w <= 0 || h <= 0 { return; }
+
+        self.src.clear();
+        self.src.extend_from_slice(fb.pixels());
+
+        let x0 = x.max(0);
+        let y0 = y.max(0);
+        let x1 = (x + w).min(fb.width);
+        let y1 = (y + h).min(fb.height);
+        if x0 >= x1 || y0 >= y1 { return; }
+
+        let pass = Pass {
+            cfg: &self.cfg,
+            lut: &self.lut,
+            saturation: self.saturation,
+            src: &self.src,
+            fb_w: fb.width,
+            rect: (x0, y0, x1, y1),
+            seed: (time_s * self.cfg.grain.fps.max(1.0)) as u32,
+            fear,
+        };
+
+        let strip_w = (fb.width as usize).div_ceil(render_threads());
+        std::thread::scope(|sc| {
+            for strip in fb.strips(strip_w) {
+                if strip.x1 <= x0 || strip.x0 >= x1 { continue; }
+                let pass = &pass;
+                sc.spawn(move || pass.run(&strip));
+            }
+        });
+    }
+}
+
+/// Datos de solo lectura de una pasada (compartidos por los hilos).
+struct Pass<'a> {
+    cfg: &'a PostFxConfig,
+    lut: &'a [[u8; 256]; 3],
+    saturation: f32,
+    src: &'a [u8],
+    fb_w: i32,
+    rect: (i32, i32, i32, i32),
+    seed: u32,
+    fear: &'a FearFrame,
+}
+
+// Ruido barato por píxel y frame
+#[inline]
+fn hash(x: i32, y: i32, s: u32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x27d4eb2d) ^ (y as u32).wrapping_mul(0x165667b1) ^ s.wrapping_mul(0x9e3779b9);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85ebca6b);
+    h ^ (h >> 13)
+}
+
+#[inline]
+fn smoothstep(e0: f32, e1: f32, x: f32) -> f32 {
+    let t = ((x - e0) / (e1 - e0).max(1e-4)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+impl Pass<'_> {
+    fn run(&self, strip: &StripView) {
+        let cfg = self.cfg;
+        let fear = self.fear;
+        let on = cfg.enabled;
+        let (rx0, ry0, rx1, ry1) = self.rect;
+        let cx = (rx0 + rx1) as f32 * 0.5;
+        let cy = (ry0 + ry1) as f32 * 0.5;
+        let inv_hw = 2.0 / (rx1 - rx0) as f32;
+        let inv_hh = 2.0 / (ry1 - ry0) as f32;
+        let inv_diag = std::f32::consts::FRAC_1_SQRT_2;
+        let grain = cfg.grain.amount * 255.0;
+
+        let vig = if on && cfg.vignette.enabled { cfg.vignette } else {
+            VignetteConfig { enabled: false, strength: 0.0, radius: 1.0, softness: 0.6 }
+        };
+        let vig_strength = (vig.strength + fear.vignette).min(1.0);
+        let desat = fear.desat.clamp(0.0, 1.0);
+        let (shx, shy) = fear.shake;
+
+        // Franja del corte (filas absolutas) si hay glitch
+        let tear = fear.tear.map(|(a, b, off)| {
+            let hgt = (ry1 - ry0) as f32;
+            (ry0 + (a * hgt) as i32, ry0 + (b * hgt) as i32, off)
+        });
+
+        let px = |x: i32, y: i32| -> usize {
+            ((y.clamp(ry0, ry1 - 1) * self.fb_w + x.clamp(rx0, rx1 - 1)) * 4) as usize
+        };
+
+        for y in ry0..ry1 {
+            let ny = (y as f32 - cy) * inv_hh;
+            let scan = if on && cfg.scanlines.enabled && (y - ry0) % cfg.scanlines.period.max(2) == 0 {
+                1.0 - cfg.scanlines.darkness
+            } else { 1.0 };
+
+            // Temblor y corte: de dónde se lee la fila
+            let sy = y + shy;
+            let tear_off = match tear { Some((a, b, off)) if y >= a && y < b => off, _ => 0 };
+
+            for x in strip.x0.max(rx0)..strip.x1.min(rx1) {
+                let nx = (x as f32 - cx) * inv_hw;
+                let sx = x + shx + tear_off;
+
+                // Aberración: R hacia fuera, B hacia dentro, más fuerte en los lados
+                let (mut r, mut g, mut b) = if on && cfg.chroma.enabled {
+                    let off = (cfg.chroma.offset_px * nx).round() as i32;
+                    (self.src[px(sx + off, sy)], self.src[px(sx, sy) + 1], self.src[px(sx - off, sy) + 2])
+                } else {
+                    let i = px(sx, sy);
+                    (self.src[i], self.src[i + 1], self.src[i + 2])
+                };
+
+                if on && cfg.grade {
+                    if self.saturation != 1.0 {
+                        let l = r as f32 * 0.299 + g as f32 * 0.587 + b as f32 * 0.114;
+                        let s = self.saturation;
+                        r = (l + (r as f32 - l) * s).clamp(0.0, 255.0) as u8;
+                        g = (l + (g as f32 - l) * s).clamp(0.0, 255.0) as u8;
+                        b = (l + (b as f32 - l) * s).clamp(0.0, 255.0) as u8;
+                    }
+                    r = self.lut[0][r as usize];
+                    g = self.lut[1][g as usize];
+                    b = self.lut[2][b as usize];
+                }
+
+                // Miedo: el color se va a gris
+                if desat > 0.0 {
+                    let l = r as f32 * 0.299 + g as f32 * 0.587 + b as f32 * 0.114;
+                    r = (r as f32 + (l - r as f32) * desat) as u8;
+                    g = (g as f32 + (l - g as f32) * desat) as u8;
+                    b = (b as f32 + (l - b as f32) * desat) as u8;
+                }
+
+                let mut k = scan;
+                if vig_strength > 0.0 {
+                    let d = (nx * nx + ny * ny).sqrt() * inv_diag;
+                    k *= 1.0 - vig_strength * smoothstep(vig.radius - vig.softness, vig.radius, d);
+                }
+
+                let n = if on && cfg.grain.enabled {
+                    ((hash(x, y, self.seed) & 0xff) as f32 / 127.5 - 1.0) * grain
+                } else { 0.0 };
+
+                let f = |c: u8| (c as f32 * k + n).clamp(0.0, 255.0) as u8;
+                strip.put_pixel_rgba(x, y, f(r), f(g), f(b), 255);
+            }
+        }
+    }
+}
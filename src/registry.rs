@@ -0,0 +1,175 @@
+// registry.rs
+// Registro de objetos: todo lo que se puede recoger y llevar encima.
+// Cada entrada dice su categoría, su glyph en el maze, sus texturas y cuántos
+// caben en el inventario. Las llaves además dicen qué puerta abren, así que
+// un color nuevo es una entrada más aquí (y sus imágenes).
+
+use crate::items::{ItemKind, FLARE_GLYPH, LURE_GLYPH, WARD_GLYPH};
+use crate::maze::Maze;
+use crate::textures::SheetLayout;
+
+/// Índice en `ITEM_DEFS`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ItemId(pub u16);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    /// Abre las puertas de su color
+    Key,
+    /// Se gasta al usarlo (Q)
+    Consumable,
+    /// Se lleva y ya (objetivos de nivel); no se gasta
+    Quest,
+}
+
+/// Qué pasa con la llave al abrir una puerta de su color (por nivel, ver level.rs).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyMode {
+    /// Una llave abre todas las puertas de su color (solo se recoge la primera)
+    Master,
+    /// Cada puerta gasta una llave; la salida pide quedarse con una de cada color
+    Consumed,
+}
+
+/// Puerta que abre una llave.
+pub struct DoorDef {
+    pub glyph: char, // sólida en el maze (y clave de textura)
+    pub img: &'static str,
+}
+
+pub struct ItemDef {
+    pub name: &'static str,
+    pub category: Category,
+    pub glyph: char,               // en el maze y clave de textura del sprite
+    pub img: &'static str,
+    pub sheet: SheetLayout,
+    pub icon: char,                // clave de textura del icono del HUD
+    pub icon_img: Option<&'static str>, // None = el mismo sprite del suelo
+    pub stack: u8,                 // máximo en el inventario
+    pub door: Option<DoorDef>,     // solo llaves
+    pub effect: Option<ItemKind>,  // solo consumibles
+    pub minimap: (u8,u8,u8,u8),
+}
+
+pub const ITEM_DEFS: &[ItemDef] = &[
+    // --- Llaves ---
+    ItemDef {
+        name: "Llave amarilla", category: Category::Key,
+        glyph: '1', img: "assets/keys1.png", sheet: SheetLayout::new(4, 2, 8.0),
+        icon: 'y', icon_img: Some("assets/keyhud_yellow.png"),
+        stack: 9,
+        door: Some(DoorDef { glyph: 'Y', img: "assets/door_yellow.png" }),
+        effect: None,
+        minimap: (255, 215, 0, 255),
+    },
+    ItemDef {
+        name: "Llave azul", category: Category::Key,
+        glyph: '2', img: "assets/keys2.png", sheet: SheetLayout::new(4, 2, 8.0),
+        icon: 'b', icon_img: Some("assets/keyhud_blue.png"),
+        stack: 9,
+        door: Some(DoorDef { glyph: 'B', img: "assets/door_blue.png" }),
+        effect: None,
+        minimap: (60, 130, 255, 255),
+    },
+    ItemDef {
+        name: "Llave roja", category: Category::Key,
+        glyph: '3', img: "assets/keys3.png", sheet: SheetLayout::new(4, 2, 8.0),
+        icon: 'r', icon_img: Some("assets/keyhud_red.png"),
+        stack: 9,
+        door: Some(DoorDef { glyph: 'R', img: "assets/door_red.png" }),
+        effect: None,
+        minimap: (235, 60, 60, 255),
+    },
+    ItemDef {
+        name: "Llave verde", category: Category::Key,
+        glyph: '4', img: "assets/keys4.png", sheet: SheetLayout::new(4, 2, 8.0),
+        icon: 'k', icon_img: Some("assets/keyhud_green.png"),
+        stack: 9,
+        door: Some(DoorDef { glyph: 'D', img: "assets/door_green.png" }),
+        effect: None,
+        minimap: (60, 200, 90, 255),
+    },
+    // --- Consumibles (ver items.rs) ---
+    ItemDef {
+        name: "Bengala", category: Category::Consumable,
+        glyph: FLARE_GLYPH, img: "assets/items/flare.png", sheet: SheetLayout::STATIC,
+        icon: FLARE_GLYPH, icon_img: None,
+        stack: 3,
+        door: None,
+        effect: Some(ItemKind::Flare),
+        minimap: (255, 120, 60, 255),
+    },
+    ItemDef {
+        name: "Cascabel", category: Category::Consumable,
+        glyph: LURE_GLYPH, img: "assets/items/lure.png", sheet: SheetLayout::STATIC,
+        icon: LURE_GLYPH, icon_img: None,
+        stack: 3,
+        door: None,
+        effect: Some(ItemKind::Lure),
+        minimap: (200, 200, 210, 255),
+    },
+    ItemDef {
+        name: "Amuleto", category: Category::Consumable,
+        glyph: WARD_GLYPH, img: "assets/items/ward.png", sheet: SheetLayout::STATIC,
+        icon: WARD_GLYPH, icon_img: None,
+        stack: 2,
+        door: None,
+        effect: Some(ItemKind::Ward),
+        minimap: (90, 220, 235, 255),
+    },
+];
+
+#[inline]
+pub fn def(id: ItemId) -> &'static ItemDef {
+    &ITEM_DEFS[id.0 as usize]
+}
+
+pub fn ids() -> impl Iterator<Item = ItemId> {
+    (0..ITEM_DEFS.len() as u16).map(ItemId)
+}
+
+pub fn of_category(cat: Category) -> impl Iterator<Item = ItemId> {
+    ids().filter(move |&id| def(id).category == cat)
+}
+
+/// Objeto que se recoge en una celda con glyph `c`.
+pub fn by_glyph(c: char) -> Option<ItemId> {
+    ids().find(|&id| def(id).glyph == c)
+}
+
+/// Llave que abre la puerta de glyph `c`.
+pub fn key_for_door(c: char) -> Option<ItemId> {
+    ids().find(|&id| def(id).door.as_ref().is_some_and(|d| d.glyph == c))
+}
+
+/// Tabla ASCII de glyphs de puerta de color, hecha en compilación a partir de `ITEM_DEFS`
+/// (`is_key_door` va en el DDA y en cada expansión de A*/flujo: nada de recorrer el registro).
+const KEY_DOOR_TABLE: [bool; 128] = {
+    let mut t = [false; 128];
+    let mut i = 0;
+    while i < ITEM_DEFS.len() {
+        if let Some(d) = &ITEM_DEFS[i].door {
+            assert!((d.glyph as u32) < 128, "glyph de puerta fuera de ASCII");
+            t[d.glyph as usize] = true;
+        }
+        i += 1;
+    }
+    t
+};
+
+/// ¿Es `c` una puerta de color (con llave)? La salida 'G' no cuenta.
+#[inline]
+pub fn is_key_door(c: char) -> bool {
+    (c as u32) < 128 && KEY_DOOR_TABLE[c as usize]
+}
+
+/// Colores de llave presentes en el maze (por llave o por puerta): los que pide la salida.
+pub fn keys_in(maze: &Maze) -> Vec<ItemId> {
+    of_category(Category::Key)
+        .filter(|&id| {
+            let d = def(id);
+            let door = d.door.as_ref().map(|d| d.glyph);
+            maze.iter().flatten().any(|&c| c == d.glyph || Some(c) == door)
+        })
+        .collect()
+}
@@ -5,6 +5,7 @@ use crate::player::Player;
 use crate::enemy::Enemy;
 use crate::sprites::Sprite; // ⬅️ para dibujar llaves
 use crate::caster::{cast_ray, Intersect};
+use crate::registry::{self, is_key_door};
 
 /// Colores configurables del minimapa (RGBA en u8).
 #[derive(Clone, Copy)]
@@ -20,9 +21,6 @@ pub struct MinimapColors {
     pub dir_line:(u8,u8,u8,u8),
     pub fov_ray: (u8,u8,u8,u8),
     pub frame:   (u8,u8,u8,u8),
-    pub key_y:   (u8,u8,u8,u8),
-    pub key_b:   (u8,u8,u8,u8),
-    pub key_r:   (u8,u8,u8,u8),
 }
 
 impl Default for MinimapColors {
@@ -39,9 +37,6 @@ impl Default for MinimapColors {
             dir_line:(180,255,180,255),
             fov_ray: (110,160,255,160),
             frame:   (0,0,0,160),
-            key_y:   (255,215,  0,255),
-            key_b:   (60, 130,255,255),
-            key_r:   (235, 60, 60,255),
         }
     }
 }
@@ -59,7 +54,7 @@ pub fn draw_cell(
         'g' => Color::RED,                    // goal (legacy)
         'e' => Color::YELLOW,
         // puertas también podrían mostrarse aquí si usas render_maze
-        'Y' | 'B' | 'R' | 'G' => Color::DARKGRAY,
+        c if c == 'G' || is_key_door(c) => Color::DARKGRAY,
         _   => Color::BLANK,                  // espacios
     };
     framebuffer.set_current_color(color);
@@ -151,8 +146,9 @@ pub fn render_minimap_zoomed(
                 '@'                      => style.wall2,
                 '#'                      => style.wall3,
                 '!'                      => style.wall4,
+                '=' | ':' | '/' | '~'    => style.wall4, // rejas/alambradas/ventanas/amuleto
                 // puertas: puedes asimilarlas a una pared “1”
-                'Y' | 'B' | 'R' | 'G'    => style.wall1,
+                c if c == 'G' || is_key_door(c) => style.wall1,
                 'g'                      => style.goal, // legacy
                 _                        => style.empty,
             };
@@ -217,12 +213,8 @@ pub fn render_minimap_zoomed(
         if kx >= start_i_f && kx <= start_i_f + vw as f32 &&
            ky >= start_j_f && ky <= start_j_f + vh as f32 {
             let (kxi, kyi) = to_px(kx, ky);
-            let color = match s.tex {
-                '1' => style.key_y, // amarilla
-                '2' => style.key_b, // azul
-                '3' => style.key_r, // roja
-                _   => style.empty,
-            };
+            // Color de la llave según el registro
+            let color = registry::by_glyph(s.tex).map_or(style.empty, |id| registry::def(id).minimap);
             // un pelín más pequeño que el player
             let kr = (pr * 2 / 3).max(2);
             draw_disc(fb, kxi, kyi, kr, color);
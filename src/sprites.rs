@@ -1,13 +1,22 @@
 use raylib::prelude::Vector2;
-use crate::framebuffer::Framebuffer;
+use crate::framebuffer::{Framebuffer, StripView, render_threads};
 use crate::player::Player;
 use crate::textures::TextureManager;
-use crate::level::Lighting;
+use crate::level::{Lighting, Fog};
+use crate::flashlight::{Beam, BATTERY_GLYPH};
+use crate::registry::{self, Category};
 
 const PI: f32 = std::f32::consts::PI;
 const TWO_PI: f32 = std::f32::consts::TAU;
-// Si tus PNGs tienen alpha real, puedes quitar el key:
-const TRANSPARENT_KEY: (u8,u8,u8) = (152,0,136);
+
+/// Cómo se compone el sprite sobre lo que hay detrás.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpriteBlend {
+    /// source-over normal (respeta alpha parcial)
+    Alpha,
+    /// suma de color: brillos, fantasmas (no lo apaga la oscuridad)
+    Additive,
+}
 
 #[derive(Clone, Copy, Debug)]
 pub struct Sprite {
@@ -17,16 +26,33 @@ pub struct Sprite {
     pub frames: usize,
     pub fps: f32,
     pub phase: usize,
+    /// Hacia dónde mira (rad). Con hojas direccionales elige el ángulo de vista.
+    pub heading: Option<f32>,
+    /// Opacidad global 0..1 (se multiplica por el alpha de la textura)
+    pub opacity: f32,
+    pub blend: SpriteBlend,
 }
 
 impl Sprite {
     pub fn new_animated(pos: Vector2, tex: char, scale: f32, frames: usize, fps: f32, phase: usize) -> Self {
-        Self { pos, tex, scale, frames: frames.max(1), fps, phase }
+        Self { pos, tex, scale, frames: frames.max(1), fps, phase, heading: None, opacity: 1.0, blend: SpriteBlend::Alpha }
     }
 }
 
+/// Fila de la hoja direccional según cómo ve la cámara al sprite (0 = de frente).
 #[inline]
-fn is_key_tex(ch: char) -> bool { ch == '1' || ch == '2' || ch == '3' }
+fn view_dir(heading: f32, sprite: Vector2, cam: Vector2, dirs: usize) -> usize {
+    let to_cam = (cam.y - sprite.y).atan2(cam.x - sprite.x);
+    let rel = normalize_angle(to_cam - heading);
+    let sector = TWO_PI / dirs as f32;
+    ((rel / sector).round() as i32).rem_euclid(dirs as i32) as usize
+}
+
+/// Categoría del objeto recogible de glyph `ch` (None si no es un objeto).
+#[inline]
+fn item_category(ch: char) -> Option<Category> {
+    registry::by_glyph(ch).map(|id| registry::def(id).category)
+}
 
 pub fn collect_sprites(maze: &Vec<Vec<char>>, block_size: usize, tex: &TextureManager) -> Vec<Sprite> {
     let mut v = Vec::new();
@@ -47,7 +73,7 @@ pub fn collect_keys(maze: &Vec<Vec<char>>, block_size: usize, tex: &TextureManag
     let mut v = Vec::new();
     for (j, row) in maze.iter().enumerate() {
         for (i, &c) in row.iter().enumerate() {
-            if is_key_tex(c) {
+            if item_category(c) == Some(Category::Key) {
                 let x = (i * block_size + block_size / 2) as f32;
                 let y = (j * block_size + block_size / 2) as f32;
                 let frames = tex.sheet_frames(c);
@@ -58,26 +84,88 @@ pub fn collect_keys(maze: &Vec<Vec<char>>, block_size: usize, tex: &TextureManag
     v
 }
 
+pub fn collect_batteries(maze: &Vec<Vec<char>>, block_size: usize, tex: &TextureManager) -> Vec<Sprite> {
+    let mut v = Vec::new();
+    for (j, row) in maze.iter().enumerate() {
+        for (i, &c) in row.iter().enumerate() {
+            if c == BATTERY_GLYPH {
+                let x = (i * block_size + block_size / 2) as f32;
+                let y = (j * block_size + block_size / 2) as f32;
+                let frames = tex.sheet_frames(c);
+                v.push(Sprite::new_animated(Vector2::new(x,y), c, 1.0, frames, 8.0, 0));
+            }
+        }
+    }
+    v
+}
+
+/// Resto de objetos del registro (consumibles, objetos de misión); `tex` es su glyph.
+pub fn collect_items(maze: &Vec<Vec<char>>, block_size: usize, tex: &TextureManager) -> Vec<Sprite> {
+    let mut v = Vec::new();
+    for (j, row) in maze.iter().enumerate() {
+        for (i, &c) in row.iter().enumerate() {
+            if item_category(c).is_some_and(|cat| cat != Category::Key) {
+                let x = (i * block_size + block_size / 2) as f32;
+                let y = (j * block_size + block_size / 2) as f32;
+                let frames = tex.sheet_frames(c);
+                v.push(Sprite::new_animated(Vector2::new(x,y), c, 1.0, frames, 8.0, 0));
+            }
+        }
+    }
+    v
+}
+
+/// Sombreado por distancia + linterna, y mezcla con la niebla si existe.
+#[inline]
+fn shade_px(r: u8, g: u8, b: u8, shade: f32, light: f32, fog: Option<&Fog>, f: f32) -> (u8, u8, u8) {
+    let (r, g, b) = ((r as f32 * shade) as u8, (g as f32 * shade) as u8, (b as f32 * shade) as u8);
+    match fog {
+        Some(fg) => fg.blend(r, g, b, light, f),
+        None     => ((r as f32 * light) as u8, (g as f32 * light) as u8, (b as f32 * light) as u8),
+    }
+}
+
 #[inline] fn normalize_angle(mut a: f32) -> f32 { while a>PI {a-=TWO_PI;} while a<(-PI) {a+=TWO_PI;} a }
 
+/// Rejas/alambradas delante de los sprites: profundidad por píxel, columna a
+/// columna, de las columnas que tienen alguna (`cols`); las demás no se miran.
+#[derive(Clone, Copy)]
+pub struct CoverView<'a> {
+    pub depth: &'a [f32],
+    pub cols: &'a [bool],
+    pub h: usize,
+}
+
+impl<'a> CoverView<'a> {
+    /// Profundidades de la columna `x` (vacío si no hay rejas en ella).
+    #[inline]
+    pub fn column(&self, x: usize) -> &'a [f32] {
+        if self.cols.get(x) == Some(&true) { &self.depth[x * self.h..(x + 1) * self.h] } else { &[] }
+    }
+}
+
+/// Por debajo de esto `render_sprites` dibuja en el hilo actual.
+const PAR_MIN_SPRITES: usize = 8;
+
 pub fn render_sprites(
     fb: &mut Framebuffer,
     player: &Player,
     sprites: &[Sprite],
     tex: &TextureManager,
     zbuf: &[f32],
+    cover: CoverView, // rejas delante (profundidad por píxel)
     block_size: usize,
     time_s: f32,
     viewport_y0: i32,
     viewport_h: i32,
     lighting: &Lighting, // NUEVO
 ) {
+    if sprites.is_empty() { return; }
+
     let w = fb.width as i32;
     let h = viewport_h.max(1);
     let y_off = viewport_y0.max(0);
-    let hw = w as f32 * 0.5;
-    let hh = h as f32 * 0.5;
-    let dist_to_plane = hw / (player.fov * 0.5).tan();
+    let beam = Beam::new(&player.light, player.fov, w, y_off, h, block_size, time_s);
 
     // Orden por distancia (lejos -> cerca)
     let mut order: Vec<(usize, f32)> = sprites.iter().enumerate()
@@ -85,7 +173,47 @@ pub fn render_sprites(
         .collect();
     order.sort_by(|a,b| b.1.partial_cmp(&a.1).unwrap());
 
-    for (idx, _euclid) in order {
+    // Cada hilo dibuja todos los sprites recortados a su franja de columnas
+    let strip_w = (w as usize).div_ceil(render_threads());
+    let ctx = SpriteCtx { player, sprites, order: &order, tex, zbuf, cover, block_size, time_s, w, y_off, h, lighting, beam: &beam };
+    // Con pocos sprites no compensa lanzar hilos: todo en una franja, aquí mismo
+    if sprites.len() < PAR_MIN_SPRITES {
+        for strip in fb.strips(w as usize) { draw_sprites_strip(&strip, &ctx); }
+        return;
+    }
+    std::thread::scope(|sc| {
+        for strip in fb.strips(strip_w) {
+            let ctx = &ctx;
+            sc.spawn(move || draw_sprites_strip(&strip, ctx));
+        }
+    });
+}
+
+/// Datos compartidos (solo lectura) por los hilos de `render_sprites`.
+struct SpriteCtx<'a> {
+    player: &'a Player,
+    sprites: &'a [Sprite],
+    order: &'a [(usize, f32)],
+    tex: &'a TextureManager,
+    zbuf: &'a [f32],
+    cover: CoverView<'a>,
+    block_size: usize,
+    time_s: f32,
+    w: i32,
+    y_off: i32,
+    h: i32,
+    lighting: &'a Lighting,
+    beam: &'a Beam,
+}
+
+fn draw_sprites_strip(strip: &StripView, ctx: &SpriteCtx) {
+    let SpriteCtx { player, sprites, order, tex, zbuf, cover, block_size, time_s, w, y_off, h, lighting, beam } = *ctx;
+    let x_max = strip.x1 - 1;
+    let hw = w as f32 * 0.5;
+    let hh = h as f32 * 0.5;
+    let dist_to_plane = hw / (player.fov * 0.5).tan();
+
+    for &(idx, _euclid) in order {
         let s = sprites[idx];
         let dx = s.pos.x - player.pos.x;
         let dy = s.pos.y - player.pos.y;
@@ -103,8 +231,8 @@ pub fn render_sprites(
 
         let screen_x = hw + (lateral * dist_to_plane) / perp;
         let base = block_size as f32 * s.scale;
-        let mut sprite_h = ((base * dist_to_plane) / perp).max(1.0);
-        let mut sprite_w = sprite_h;
+        let sprite_h = ((base * dist_to_plane) / perp).max(1.0);
+        let sprite_w = sprite_h;
 
         let left = screen_x - sprite_w * 0.5;
         let top  = (y_off as f32) + hh - sprite_h * 0.5;
@@ -114,25 +242,30 @@ pub fn render_sprites(
         let start_y_raw = top.floor() as i32;
         let end_y_raw   = (top + sprite_h).ceil() as i32 - 1;
 
-        // Clip a viewport
+        // Clip a viewport (y) y a la franja de este hilo (x)
         let y_min = y_off;
         let y_max = y_off + h - 1;
 
-        if end_x_raw < 0 || start_x_raw > (w-1) || end_y_raw < y_min || start_y_raw > y_max {
+        if end_x_raw < strip.x0 || start_x_raw > x_max || end_y_raw < y_min || start_y_raw > y_max {
             continue;
         }
 
-        let mut start_x = start_x_raw.max(0);
-        let mut end_x   = end_x_raw.min(w-1);
-        let mut start_y = start_y_raw.max(y_min);
-        let mut end_y   = end_y_raw.min(y_max);
+        let start_x = start_x_raw.max(strip.x0);
+        let end_x   = end_x_raw.min(x_max);
+        let start_y = start_y_raw.max(y_min);
+        let end_y   = end_y_raw.min(y_max);
         if start_x > end_x || start_y > end_y { continue; }
 
-        let anim_fps = s.fps.max(1.0);
-        let frame_i = (time_s * anim_fps).floor() as usize;
-        let frame = if s.frames>1 { (frame_i + s.phase) % s.frames } else { 0 };
+        // fps <= 0: frame fijo (ángel congelado, hojas que no son animación)
+        let frame_i = if s.fps > 0.0 { (time_s * s.fps).floor() as usize } else { 0 };
+        let mut frame = if s.frames>1 { (frame_i + s.phase) % s.frames } else { 0 };
+        let dirs = tex.sheet_dirs(s.tex);
+        if let Some(h) = s.heading && dirs > 1 {
+            frame += view_dir(h, s.pos, player.pos, dirs) * s.frames;
+        }
 
-        let (tw, th, x0, y0, fw_us, fh_us, tdata) = tex.sheet_frame_view(s.tex, frame);
+        let (_tw, _th, x0, y0, fw_us, fh_us, _) = tex.sheet_frame_view(s.tex, frame);
+        let mip = tex.mip_sampler(s.tex, fh_us as f32 / sprite_h);
         let fw = fw_us as i32;
         let fh = fh_us as i32;
 
@@ -140,11 +273,17 @@ pub fn render_sprites(
         let step_ty = fh as f32 / sprite_h;
 
         let mut tex_xf = (start_x as f32 - left) * step_tx;
-        let mut tex_yf_start = (start_y as f32 - top) * step_ty;
+        let tex_yf_start = (start_y as f32 - top) * step_ty;
 
         let shade = (1.0 / (1.0 + perp * lighting.atten)).clamp(lighting.shade_min, 1.0);
+        let fog = lighting.fog.as_ref();
+        let fog_f = fog.map_or(0.0, |f| f.factor(perp / block_size as f32));
 
-        let mut step_x_cols = 1;
+        // Opacidad en 0..256 para escalar alpha con un shift; sprites opacos sin mezcla
+        let op = (s.opacity.clamp(0.0, 1.0) * 256.0) as u32;
+        if op == 0 { continue; }
+        let additive = s.blend == SpriteBlend::Additive;
+        let solid = !additive && op >= 256;
 
         let mut x = start_x;
         while x <= end_x {
@@ -155,69 +294,46 @@ pub fn render_sprites(
 
                 let mut y = start_y;
                 let mut tex_yf = tex_yf_start;
+                let cover_col = cover.column(x as usize);
                 while y <= end_y {
+                    // Tapado por una reja más cercana
+                    if !cover_col.is_empty() && cover_col[(y - y_off) as usize] < perp {
+                        y += 1;
+                        tex_yf += step_ty;
+                        continue;
+                    }
+
                     let mut ty = tex_yf.floor() as i32;
                     if ty < 0 { ty = 0; }
                     if ty >= fh { ty = fh - 1; }
 
                     let px = x0 as i32 + tex_x;
                     let py = y0 as i32 + ty;
-                    let idx = (((py as usize) * tw) + (px as usize)) * 4;
-
-                    let r = tdata[idx];
-                    let g = tdata[idx + 1];
-                    let b = tdata[idx + 2];
-                    let a = tdata[idx + 3];
-
-                    if !(a == 0 || (r,g,b) == TRANSPARENT_KEY) {
-                        let rr = (r as f32 * shade) as u8;
-                        let gg = (g as f32 * shade) as u8;
-                        let bb = (b as f32 * shade) as u8;
-                        fb.put_pixel_rgba(x, y, rr, gg, bb, a);
+                    let (r, g, b, a) = mip.texel(px as usize, py as usize);
+
+                    if a != 0 {
+                        if additive {
+                            // Emisivo: la linterna no le afecta, la niebla sí
+                            let (rr, gg, bb) = shade_px(r, g, b, 1.0, 1.0, fog, fog_f);
+                            strip.add_pixel(x, y, rr, gg, bb, ((a as u32 * op) >> 8) as u8);
+                        } else {
+                            let light = beam.light_at(x, y, perp);
+                            let (rr, gg, bb) = shade_px(r, g, b, shade, light, fog, fog_f);
+                            if solid && a == 255 {
+                                strip.put_pixel_rgba(x, y, rr, gg, bb, 255);
+                            } else {
+                                strip.blend_pixel(x, y, rr, gg, bb, ((a as u32 * op) >> 8).min(255) as u8);
+                            }
+                        }
                     }
 
                     y += 1;
                     tex_yf += step_ty;
                 }
-
-                if step_x_cols > 1 {
-                    for rx in 1..step_x_cols {
-                        let xx = x + rx as i32;
-                        if xx > end_x { break; }
-                        if zbuf[xx as usize] <= perp { break; }
-
-                        let mut y2 = start_y;
-                        let mut tex_yf2 = tex_yf_start;
-                        while y2 <= end_y {
-                            let mut ty = tex_yf2.floor() as i32;
-                            if ty < 0 { ty = 0; }
-                            if ty >= fh { ty = fh - 1; }
-
-                            let px = x0 as i32 + tex_x;
-                            let py = y0 as i32 + ty;
-                            let idx = (((py as usize) * tw) + (px as usize)) * 4;
-
-                            let r = tdata[idx];
-                            let g = tdata[idx + 1];
-                            let b = tdata[idx + 2];
-                            let a = tdata[idx + 3];
-
-                            if !(a == 0 || (r,g,b) == TRANSPARENT_KEY) {
-                                let rr = (r as f32 * shade) as u8;
-                                let gg = (g as f32 * shade) as u8;
-                                let bb = (b as f32 * shade) as u8;
-                                fb.put_pixel_rgba(xx, y2, rr, gg, bb, a);
-                            }
-
-                            y2 += 1;
-                            tex_yf2 += step_ty;
-                        }
-                    }
-                }
             }
 
-            x += step_x_cols as i32;
-            tex_xf += step_tx * step_x_cols as f32;
+            x += 1;
+            tex_xf += step_tx;
         }
     }
 }
@@ -0,0 +1,69 @@
+// stealth.rs
+// Cuánto se deja ver el jugador: luz (linterna + ambiente del tema) y movimiento.
+// Los enemigos usan `visibility` (ya aplican su propia distancia); el HUD
+// muestra `level`, que además cuenta lo cerca que está el enemigo más próximo.
+
+use crate::controller::Movement;
+
+#[derive(Clone, Copy)]
+pub struct StealthConfig {
+    pub still: f32,        // multiplicador quieto
+    pub walk: f32,         // caminando
+    pub sprint: f32,       // corriendo (SHIFT)
+    pub dark: f32,         // visibilidad mínima a oscuras y sin linterna
+    pub flashlight: f32,   // cuánto suma la linterna a plena intensidad
+    pub near_cells: f32,   // a esta distancia (o menos) el enemigo cuenta entero
+    pub far_cells: f32,    // a partir de aquí ya no cuenta para el medidor
+    pub smoothing: f32,    // velocidad de respuesta del medidor (1/seg)
+}
+
+impl Default for StealthConfig {
+    fn default() -> Self {
+        Self {
+            still: 0.35,
+            walk: 0.7,
+            sprint: 1.0,
+            dark: 0.25,
+            flashlight: 0.6,
+            near_cells: 1.5,
+            far_cells: 8.0,
+            smoothing: 4.0,
+        }
+    }
+}
+
+pub struct Stealth {
+    pub cfg: StealthConfig,
+    pub visibility: f32, // 0..1, sin distancia (para los enemigos)
+    pub level: f32,      // 0..1, suavizado y con distancia (para el HUD)
+}
+
+impl Stealth {
+    pub fn new(cfg: StealthConfig) -> Self {
+        Self { cfg, visibility: 0.0, level: 0.0 }
+    }
+
+    pub fn reset(&mut self) {
+        self.visibility = 0.0;
+        self.level = 0.0;
+    }
+
+    /// `light`: intensidad actual de la linterna (0..1).
+    /// `ambient`: luz propia del tema (0..1).
+    /// `nearest_cells`: distancia al enemigo más cercano, en celdas.
+    pub fn update(&mut self, dt: f32, movement: Movement, light: f32, ambient: f32, nearest_cells: f32) {
+        let c = self.cfg;
+        let lit = (c.dark + ambient * (1.0 - c.dark) * 0.5 + light * c.flashlight).min(1.0);
+        let moving = match movement {
+            Movement::Still  => c.still,
+            Movement::Walk   => c.walk,
+            Movement::Sprint => c.sprint,
+        };
+        self.visibility = (lit * moving).clamp(0.0, 1.0);
+
+        let near = 1.0 - ((nearest_cells - c.near_cells) / (c.far_cells - c.near_cells).max(1e-3)).clamp(0.0, 1.0);
+        let target = self.visibility * near;
+        let k = 1.0 - (-c.smoothing * dt).exp();
+        self.level += (target - self.level) * k;
+    }
+}
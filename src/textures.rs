@@ -2,17 +2,158 @@ use raylib::prelude::*;
 use raylib::consts::PixelFormat;
 use std::collections::HashMap;
 
-use crate::level::LevelTheme;
+use crate::level::{LevelTheme, SKY_GLYPH};
+use crate::archetype::ALL_ARCHETYPES;
+use crate::registry::ITEM_DEFS;
 
 #[derive(Clone, Copy)]
-pub struct TexSheet { pub cols: usize, pub rows: usize, pub frame_w: usize, pub frame_h: usize }
+pub struct TexSheet { pub cols: usize, pub rows: usize, pub frame_w: usize, pub frame_h: usize, pub fps: f32, pub dirs: usize }
 
-pub struct TexturePixels { pub w: usize, pub h: usize, pub data: Vec<u8> }
+/// Layout declarado de una hoja: rejilla de frames y velocidad por defecto.
+/// Con `dirs` > 1 la hoja trae una animación por ángulo de vista (una fila cada uno).
+#[derive(Clone, Copy)]
+pub struct SheetLayout { pub cols: usize, pub rows: usize, pub fps: f32, pub dirs: usize }
+
+impl SheetLayout {
+    /// Imagen simple, sin animación.
+    pub const STATIC: SheetLayout = SheetLayout { cols: 1, rows: 1, fps: 0.0, dirs: 1 };
+
+    pub const fn new(cols: usize, rows: usize, fps: f32) -> Self { Self { cols, rows, fps, dirs: 1 } }
+
+    /// Hoja estilo Doom: 8 filas (ángulo de vista, 0 = de frente) de `frames` columnas.
+    pub const fn directional(frames: usize, fps: f32) -> Self { Self { cols: frames, rows: 8, fps, dirs: 8 } }
+
+    fn is_static(&self) -> bool { self.cols * self.rows <= 1 }
+}
+
+// Hojas de las texturas base (las de tema se declaran en LevelTheme)
+const BASE_SHEETS: &[(char, SheetLayout)] = &[
+    ('e', SheetLayout::new(4, 2, 8.0)),  // enemy
+    ('v', SheetLayout::directional(4, 8.0)), // arquetipos (ver archetype.rs)
+    ('w', SheetLayout::directional(4, 8.0)),
+    ('t', SheetLayout::directional(4, 8.0)),
+    ('u', SheetLayout::directional(4, 8.0)),
+    ('f', SheetLayout::new(4, 2, 8.0)),  // cara del HUD
+    ('F', SheetLayout::new(4, 1, 12.0)), // bengala encendida
+    ('n', SheetLayout::new(10, 1, 0.0)), // dígitos FPS (se eligen, no se animan)
+];
+
+// Color key de transparencia de los sprites (además del alpha real)
+pub(crate) const TRANSPARENT_KEY: (u8,u8,u8) = (152,0,136);
+
+/// Un nivel de la cadena de mipmaps (1/2, 1/4, ... del original).
+pub struct MipLevel { pub w: usize, pub h: usize, pub data: Vec<u8> }
+
+pub struct TexturePixels {
+    pub w: usize,
+    pub h: usize,
+    pub data: Vec<u8>,
+    /// mips[0] = mitad de tamaño, mips[1] = cuarto, ... hasta 1x1
+    pub mips: Vec<MipLevel>,
+}
+
+impl TexturePixels {
+    pub fn new(w: usize, h: usize, mut data: Vec<u8>) -> Self {
+        // El color key pasa a alpha 0: a partir de aquí sólo cuenta el alpha
+        for px in data.chunks_exact_mut(4) {
+            if (px[0], px[1], px[2]) == TRANSPARENT_KEY { px[3] = 0; }
+        }
+
+        let mut mips: Vec<MipLevel> = Vec::new();
+        let (mut pw, mut ph) = (w, h);
+        while pw > 1 || ph > 1 {
+            let src = mips.last().map(|m| &m.data[..]).unwrap_or(&data[..]);
+            let next = downsample_2x2(src, pw, ph);
+            pw = next.w;
+            ph = next.h;
+            mips.push(next);
+        }
+        Self { w, h, data, mips }
+    }
+
+    /// Nivel `l` (0 = original), con clamp al más pequeño.
+    #[inline]
+    pub fn level(&self, l: usize) -> (usize, usize, &[u8]) {
+        if l == 0 || self.mips.is_empty() { return (self.w, self.h, &self.data); }
+        let m = &self.mips[(l - 1).min(self.mips.len() - 1)];
+        (m.w, m.h, &m.data)
+    }
+}
+
+/// Box filter 2x2. Los texeles transparentes (alpha 0) no aportan color,
+/// sólo cobertura, para no teñir los bordes de magenta.
+fn downsample_2x2(src: &[u8], w: usize, h: usize) -> MipLevel {
+    let nw = (w / 2).max(1);
+    let nh = (h / 2).max(1);
+    let mut data = vec![0u8; nw * nh * 4];
+
+    for y in 0..nh {
+        for x in 0..nw {
+            let (mut r, mut g, mut b, mut a, mut n) = (0u32, 0u32, 0u32, 0u32, 0u32);
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let sx = (x * 2 + dx).min(w - 1);
+                let sy = (y * 2 + dy).min(h - 1);
+                let i = (sy * w + sx) * 4;
+                let (sr, sg, sb, sa) = (src[i], src[i + 1], src[i + 2], src[i + 3]);
+                if sa == 0 { continue; }
+                r += sr as u32; g += sg as u32; b += sb as u32; a += sa as u32;
+                n += 1;
+            }
+            let o = (y * nw + x) * 4;
+            if n == 0 {
+                data[o..o + 4].copy_from_slice(&[TRANSPARENT_KEY.0, TRANSPARENT_KEY.1, TRANSPARENT_KEY.2, 0]);
+            } else {
+                data[o]     = (r / n) as u8;
+                data[o + 1] = (g / n) as u8;
+                data[o + 2] = (b / n) as u8;
+                data[o + 3] = (a / 4) as u8;
+            }
+        }
+    }
+    MipLevel { w: nw, h: nh, data }
+}
+
+/// Muestreo con mipmaps: coords en texeles del nivel 0, escaladas al nivel elegido
+/// y, si `t > 0`, mezcladas con el siguiente nivel.
+pub struct MipSampler<'a> {
+    base_w: usize,
+    base_h: usize,
+    a: (usize, usize, &'a [u8]),
+    b: (usize, usize, &'a [u8]),
+    t: f32,
+}
+
+impl MipSampler<'_> {
+    #[inline]
+    fn fetch(lv: (usize, usize, &[u8]), bw: usize, bh: usize, x: usize, y: usize) -> (u8, u8, u8, u8) {
+        let (w, h, d) = lv;
+        let lx = (x * w / bw).min(w - 1);
+        let ly = (y * h / bh).min(h - 1);
+        let i = (ly * w + lx) * 4;
+        (d[i], d[i + 1], d[i + 2], d[i + 3])
+    }
+
+    #[inline]
+    pub fn texel(&self, x: usize, y: usize) -> (u8, u8, u8, u8) {
+        let pa = Self::fetch(self.a, self.base_w, self.base_h, x, y);
+        if self.t <= 0.0 { return pa; }
+        let pb = Self::fetch(self.b, self.base_w, self.base_h, x, y);
+        // Con transparencia de por medio no mezclamos: nivel más cercano
+        if pa.3 == 0 || pb.3 == 0 { return if self.t < 0.5 { pa } else { pb }; }
+        let t = self.t;
+        let mix = |u: u8, v: u8| (u as f32 + (v as f32 - u as f32) * t) as u8;
+        (mix(pa.0, pb.0), mix(pa.1, pb.1), mix(pa.2, pb.2), mix(pa.3, pb.3))
+    }
+}
 
 pub struct TextureManager {
     pub(crate) pixels: HashMap<char, TexturePixels>,
     pub(crate) _textures: HashMap<char, Texture2D>,
     sheets: HashMap<char, TexSheet>,
+    /// Usar mipmaps al muestrear muros/sprites (anti-shimmer a distancia)
+    pub mipmaps: bool,
+    /// Mezclar entre niveles de mip (más suave, ~2x de lecturas)
+    pub mip_blend: bool,
 }
 
 impl TextureManager {
@@ -21,7 +162,7 @@ impl TextureManager {
         let mut textures = HashMap::new();
 
         // Cargamos un set base (placeholders) que luego podremos sobrescribir por tema
-        let texture_files = vec![
+        let mut texture_files = vec![
             ('+', "assets/wall1.png"),
             ('-', "assets/wall1.png"),
             ('|', "assets/wall1.png"),
@@ -35,20 +176,24 @@ impl TextureManager {
             ('h', "assets/hud_bg.png"),
             ('f', "assets/face.png"),
 
-            // Keys in world
-            ('1', "assets/keys1.png"),
-            ('2', "assets/keys2.png"),
-            ('3', "assets/keys3.png"),
+            // Pila (recarga de linterna)
+            ('%', "assets/battery.png"),
 
-            // HUD icons (llaves)
-            ('y', "assets/keyhud_yellow.png"),
-            ('b', "assets/keyhud_blue.png"),
-            ('r', "assets/keyhud_red.png"),
+            // Consumibles en uso y barrera del amuleto (los del suelo vienen del registro)
+            ('F', "assets/items/flare_lit.png"),
 
-            // Doors
-            ('Y', "assets/door_yellow.png"),
-            ('B', "assets/door_blue.png"),
-            ('R', "assets/door_red.png"),
+            // Bloques transparentes (el rayo sigue detrás)
+            ('=', "assets/bars.png"),
+            (':', "assets/fence.png"),
+            ('/', "assets/window_broken.png"),
+            ('~', "assets/items/ward_wall.png"),
+
+            // Decals de muro
+            ('x', "assets/decals/scratch.png"),
+            ('z', "assets/decals/blood.png"),
+            ('q', "assets/decals/note.png"),
+
+            // Puerta de salida (las de color vienen del registro)
             ('G', "assets/door_exit.png"),
 
             // FPS digits
@@ -62,6 +207,20 @@ impl TextureManager {
             ('K', "assets/ui/lock.png"),
         ];
 
+        // Sprites y jumpscares propios de cada arquetipo de enemigo
+        for a in ALL_ARCHETYPES {
+            let def = a.def();
+            if let Some(path) = def.sprite { texture_files.push((def.glyph, path)); }
+            if let Some(path) = def.jumpscare_img { texture_files.push((def.jumpscare, path)); }
+        }
+
+        // Objetos del registro: sprite del suelo, icono del HUD y puerta (llaves)
+        for d in ITEM_DEFS {
+            texture_files.push((d.glyph, d.img));
+            if let Some(path) = d.icon_img { texture_files.push((d.icon, path)); }
+            if let Some(door) = &d.door { texture_files.push((door.glyph, door.img)); }
+        }
+
         for (ch, path) in texture_files {
             let mut image = Image::load_image(path).expect(&format!("load {}", path));
             if image.format() != PixelFormat::PIXELFORMAT_UNCOMPRESSED_R8G8B8A8 {
@@ -75,44 +234,37 @@ impl TextureManager {
             let data = unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec();
 
             textures.insert(ch, tex);
-            pixels.insert(ch, TexturePixels { w, h, data });
+            pixels.insert(ch, TexturePixels::new(w, h, data));
         }
 
-        let mut sheets = HashMap::new();
-
-        // Enemy 'e' (ajusta a tu asset real)
-        if let Some(p) = pixels.get(&'e') {
-            let cols = 4; let rows = 2;
-            sheets.insert('e', TexSheet { cols, rows, frame_w: p.w / cols, frame_h: p.h / rows });
+        let mut tm = Self { pixels, _textures: textures, sheets: HashMap::new(), mipmaps: true, mip_blend: false };
+        for &(ch, layout) in BASE_SHEETS {
+            tm.set_sheet(ch, layout);
         }
-
-        // Face 'f'
-        if let Some(p) = pixels.get(&'f') {
-            let cols = 4; let rows = 2;
-            sheets.insert('f', TexSheet { cols, rows, frame_w: p.w / cols, frame_h: p.h / rows });
+        for d in ITEM_DEFS {
+            tm.set_sheet(d.glyph, d.sheet);
         }
+        tm
+    }
 
-        // Keys '1','2','3'
-        if let Some(p) = pixels.get(&'1') {
-            let cols = 4; let rows = 2;
-            sheets.insert('1', TexSheet { cols, rows, frame_w: p.w / cols, frame_h: p.h / rows });
-        }
-        if let Some(p) = pixels.get(&'2') {
-            let cols = 4; let rows = 2;
-            sheets.insert('2', TexSheet { cols, rows, frame_w: p.w / cols, frame_h: p.h / rows });
-        }
-        if let Some(p) = pixels.get(&'3') {
-            let cols = 4; let rows = 2;
-            sheets.insert('3', TexSheet { cols, rows, frame_w: p.w / cols, frame_h: p.h / rows });
+    /// Declara (o quita, si es `STATIC`) el layout de hoja de `ch`.
+    pub fn set_sheet(&mut self, ch: char, layout: SheetLayout) {
+        let Some(p) = self.pixels.get(&ch) else { return; };
+        if layout.is_static() {
+            self.sheets.remove(&ch);
+            return;
         }
+        let (cols, rows) = (layout.cols, layout.rows);
+        let dirs = layout.dirs.max(1);
+        self.sheets.insert(ch, TexSheet { cols, rows, frame_w: p.w / cols, frame_h: p.h / rows, fps: layout.fps, dirs });
+    }
 
-        // FPS digits 'n'
-        if let Some(p) = pixels.get(&'n') {
-            let cols = 10; let rows = 1;
-            sheets.insert('n', TexSheet { cols, rows, frame_w: p.w / cols, frame_h: p.h / rows });
+    /// Frame de la animación por defecto de `ch` en el instante `time_s`.
+    pub fn anim_frame(&self, ch: char, time_s: f32) -> usize {
+        match self.sheet_meta(ch) {
+            Some(s) if s.fps > 0.0 => (time_s * s.fps).floor() as usize % (s.cols * s.rows / s.dirs),
+            _ => 0,
         }
-
-        Self { pixels, _textures: textures, sheets }
     }
 
     pub fn tex_size(&self, ch: char) -> (u32, u32) {
@@ -122,6 +274,11 @@ impl TextureManager {
             .unwrap_or((64, 64))
     }
 
+    /// ¿Hay textura cargada para `ch`? (sin fallback)
+    pub fn has(&self, ch: char) -> bool {
+        self.pixels.contains_key(&ch)
+    }
+
     /// Vista de la imagen completa (w,h,data).
     pub fn tex_view(&self, ch: char) -> (usize, usize, &[u8]) {
         if let Some(p) = self.pixels.get(&ch)       { (p.w, p.h, &p.data) }
@@ -129,11 +286,32 @@ impl TextureManager {
         else { (1, 1, &[255, 255, 255, 255][..]) }
     }
 
+    /// Sampler con el nivel de mip adecuado para `texels_per_px` texeles
+    /// (del nivel 0) por píxel en pantalla.
+    pub fn mip_sampler(&self, ch: char, texels_per_px: f32) -> MipSampler<'_> {
+        let lod = if self.mipmaps { texels_per_px.max(1.0).log2() } else { 0.0 };
+        let l = lod.floor() as usize;
+        let t = if self.mip_blend { lod - l as f32 } else { 0.0 };
+
+        match self.pixels.get(&ch).or_else(|| self.pixels.get(&'#')) {
+            Some(p) => MipSampler { base_w: p.w, base_h: p.h, a: p.level(l), b: p.level(l + 1), t },
+            None => {
+                let white = (1, 1, &[255u8, 255, 255, 255][..]);
+                MipSampler { base_w: 1, base_h: 1, a: white, b: white, t: 0.0 }
+            }
+        }
+    }
+
     pub fn sheet_meta(&self, ch: char) -> Option<TexSheet> { self.sheets.get(&ch).copied() }
 
-    /// Cantidad total de frames en la hoja (o 1 si no hay hoja).
+    /// Frames de la animación (por ángulo de vista si es direccional; 1 si no hay hoja).
     pub fn sheet_frames(&self, ch: char) -> usize {
-        if let Some(s) = self.sheet_meta(ch) { s.cols * s.rows } else { 1 }
+        if let Some(s) = self.sheet_meta(ch) { s.cols * s.rows / s.dirs } else { 1 }
+    }
+
+    /// Ángulos de vista de la hoja (1 = no direccional).
+    pub fn sheet_dirs(&self, ch: char) -> usize {
+        self.sheet_meta(ch).map_or(1, |s| s.dirs)
     }
 
     /// Devuelve metadata para un frame del spritesheet:
@@ -174,7 +352,7 @@ impl TextureManager {
             let ptr = image.data as *const u8;
             let data = unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec();
             self._textures.insert(ch, tex);
-            self.pixels.insert(ch, TexturePixels { w, h, data });
+            self.pixels.insert(ch, TexturePixels::new(w, h, data));
         };
 
         // Walls:
@@ -196,10 +374,19 @@ impl TextureManager {
         load_into('C', theme.img_card3);
         load_into('K', theme.img_lock);
 
-        // Si el spritesheet del enemy cambia de layout, ajusta aquí:
-        if let Some(p) = self.pixels.get(&'e') {
-            let cols = 4; let rows = 2; // <- cambia si tu hoja difiere
-            self.sheets.insert('e', TexSheet { cols, rows, frame_w: p.w / cols, frame_h: p.h / rows });
+        // Cielo panorámico (opcional por tema)
+        match theme.sky_tex {
+            Some(path) => load_into(SKY_GLYPH, path),
+            None => { self.pixels.remove(&SKY_GLYPH); self._textures.remove(&SKY_GLYPH); }
+        }
+
+        // Layouts de hoja declarados por el tema (muros animados, enemy)
+        for (ch, layout) in [
+            ('+', theme.wall1_sheet), ('-', theme.wall1_sheet), ('|', theme.wall1_sheet),
+            ('@', theme.wall2_sheet), ('#', theme.wall3_sheet), ('!', theme.wall4_sheet),
+            ('e', theme.enemy_sheet),
+        ] {
+            self.set_sheet(ch, layout);
         }
     }
 }
@@ -1,17 +1,22 @@
 use raylib::prelude::*;
-use crate::framebuffer::Framebuffer;
+use crate::framebuffer::{Framebuffer, StripView, render_threads};
 use crate::maze::Maze;
 use crate::player::Player;
-use crate::caster::cast_ray;
-use crate::textures::TextureManager;
-use crate::sprites::{Sprite, render_sprites};
+use crate::caster::{cast_ray_layers, Intersect, MAX_SEE_THROUGH};
+use crate::textures::{TextureManager, MipSampler};
+use crate::sprites::{CoverView, Sprite, SpriteBlend, render_sprites};
 use crate::enemy::Enemy;
-use crate::level::LevelTheme;
+use crate::level::{LevelTheme, Fog, SKY_GLYPH};
+use crate::flashlight::Beam;
+use crate::decals::DecalSet;
 
-fn sky_floor_region(fb: &mut Framebuffer, y0: i32, vh: i32, sky: Color, floor: Color) {
-    let half = vh / 2;
-    for i in 0..half { fb.fill_row(y0 + i, sky); }
-    for i in half..vh { fb.fill_row(y0 + i, floor); }
+/// Aplica luz y, si hay, niebla con factor `f`.
+#[inline]
+fn lit(r: u8, g: u8, b: u8, light: f32, fog: Option<&Fog>, f: f32) -> (u8, u8, u8) {
+    match fog {
+        Some(fg) => fg.blend(r, g, b, light, f),
+        None     => ((r as f32 * light) as u8, (g as f32 * light) as u8, (b as f32 * light) as u8),
+    }
 }
 
 /// Overlay en pantalla completa
@@ -68,6 +73,25 @@ pub fn draw_win_background(fb: &mut Framebuffer) {
 }
 
 /// Render 3D en un viewport, con sprites y llaves ocluidas por muros, y tema por nivel.
+/// Buffers del render 3D que se guardan entre frames: solo cambian de tamaño
+/// con el viewport (nada de reservar y rellenar una pantalla de f32 cada frame).
+#[derive(Default)]
+pub struct WorldBuffers {
+    zbuf: Vec<f32>,
+    /// Profundidad por píxel de rejas/alambradas, columna a columna (cada franja
+    /// es un trozo contiguo); solo vale en las columnas marcadas en `covered`
+    cover: Vec<f32>,
+    covered: Vec<bool>,
+}
+
+impl WorldBuffers {
+    fn fit(&mut self, w: usize, h: usize) {
+        self.zbuf.resize(w, f32::INFINITY);
+        self.cover.resize(w * h, f32::INFINITY);
+        self.covered.resize(w, false);
+    }
+}
+
 pub fn render_world_textured(
     fb: &mut Framebuffer,
     maze: &Maze,
@@ -77,10 +101,14 @@ pub fn render_world_textured(
     sprites: &[Sprite],
     enemies: &[Enemy],
     keys_sprites: &[Sprite],
+    battery_sprites: &[Sprite],
+    item_sprites: &[Sprite], // consumibles del suelo + bengalas/cascabeles en uso
+    decals: &DecalSet,
     time_s: f32,
     viewport_y0: i32,
     viewport_h: i32,
     theme: &LevelTheme, // NUEVO
+    bufs: &mut WorldBuffers,
 ) {
     let w = fb.width as i32;
     let h = viewport_h.max(1);
@@ -90,19 +118,120 @@ pub fn render_world_textured(
     let hh = h as f32 * 0.5;
     let dist_to_plane = hw / (player.fov * 0.5).tan();
 
-    // Fondo cielo/suelo dentro del viewport según tema
-    sky_floor_region(fb, y_off, h, theme.sky, theme.floor);
+    // Linterna: cono proyectado en pantalla (fuera del cono, casi negro)
+    let beam = Beam::new(&player.light, player.fov, w, y_off, h, block_size, time_s);
+    // Distancia al suelo/techo por fila: cámara a media altura de bloque
+    let cam_h = block_size as f32 * 0.5;
+    let bs = block_size as f32;
+
+    // Niebla: distancia y factor por fila del viewport (no dependen de la columna)
+    let fog = theme.lighting.fog.as_ref();
+    let sky_fog = fog.filter(|f| f.tint_sky);
+    let mut row_dist = vec![0.0f32; h as usize];
+    let mut row_fog  = vec![0.0f32; h as usize];
+    for r in 0..h {
+        let d = cam_h * dist_to_plane / (r as f32 + 0.5 - hh).abs().max(0.5);
+        row_dist[r as usize] = d;
+        row_fog[r as usize] = fog.map_or(0.0, |f| f.factor(d / bs));
+    }
+
+    // Cielo panorámico: 360° en el ancho de la textura, la mitad superior del viewport en su alto.
+    // Sin pitch el horizonte está en el centro; con pitch bastaría desplazar `horizon`.
+    let horizon = hh;
+    let sky_pano = tex.has(SKY_GLYPH).then(|| {
+        let (sw, sh) = tex.tex_size(SKY_GLYPH);
+        let texels_per_px = (sw as f32 * player.fov / std::f32::consts::TAU / w as f32).max(sh as f32 / horizon.max(1.0));
+        tex.mip_sampler(SKY_GLYPH, texels_per_px)
+    });
+
+    // z-buffer por columna; cada hilo rellena su franja de columnas (y su trozo de zbuf).
+    // `cover`: rejas/alambradas delante (ver `WorldBuffers`); los sprites detrás no se pintan encima.
+    bufs.fit(w as usize, h as usize);
+    let WorldBuffers { zbuf, cover, covered } = bufs;
+    let strip_w = (w as usize).div_ceil(render_threads());
+    let ctx = ColumnCtx {
+        maze, player, block_size, tex, theme, time_s, decals, beam: &beam, fog, sky_fog,
+        row_dist: &row_dist, row_fog: &row_fog, w, y_off, h, dist_to_plane,
+        sky_pano: sky_pano.as_ref(), horizon,
+    };
+    std::thread::scope(|sc| {
+        let chunks = zbuf.chunks_mut(strip_w)
+            .zip(cover.chunks_mut(strip_w * h as usize))
+            .zip(covered.chunks_mut(strip_w));
+        for (strip, ((zchunk, cchunk), kchunk)) in fb.strips(strip_w).into_iter().zip(chunks) {
+            let ctx = &ctx;
+            sc.spawn(move || render_columns(&strip, zchunk, cchunk, kchunk, ctx));
+        }
+    });
+
+    // Todos los sprites del frame en una sola pasada (un solo reparto en hilos,
+    // y el orden lejos -> cerca vale también entre listas):
+    // decorativos, llaves, pilas, consumibles y enemigos
+    let mut frame_sprites: Vec<Sprite> = Vec::with_capacity(
+        sprites.len() + keys_sprites.len() + battery_sprites.len() + item_sprites.len() + enemies.len()
+    );
+    frame_sprites.extend_from_slice(sprites);
+    frame_sprites.extend_from_slice(keys_sprites);
+    frame_sprites.extend_from_slice(battery_sprites);
+    frame_sprites.extend_from_slice(item_sprites);
+    for e in enemies {
+        let glyph = e.kind.def().glyph;
+        frame_sprites.push(Sprite {
+            pos: e.pos,
+            tex: glyph,
+            scale: 1.0,
+            frames: tex.sheet_frames(glyph),
+            fps: if e.frozen { 0.0 } else { 8.0 }, // el ángel se queda como estatua
+            phase: 0,
+            heading: Some(e.heading),
+            opacity: 1.0,
+            blend: SpriteBlend::Alpha,
+        });
+    }
+    let cover = CoverView { depth: cover, cols: covered, h: h as usize };
+    render_sprites(fb, player, &frame_sprites, tex, zbuf, cover, block_size, time_s, y_off, h, &theme.lighting);
+}
+
+/// Datos compartidos (solo lectura) por los hilos del render de columnas.
+struct ColumnCtx<'a> {
+    maze: &'a Maze,
+    player: &'a Player,
+    block_size: usize,
+    tex: &'a TextureManager,
+    theme: &'a LevelTheme,
+    time_s: f32,
+    decals: &'a DecalSet,
+    beam: &'a Beam,
+    fog: Option<&'a Fog>,
+    sky_fog: Option<&'a Fog>,
+    row_dist: &'a [f32],
+    row_fog: &'a [f32],
+    w: i32,
+    y_off: i32,
+    h: i32,
+    dist_to_plane: f32,
+    sky_pano: Option<&'a MipSampler<'a>>,
+    horizon: f32,
+}
 
-    // z-buffer por columna
-    let mut zbuf = vec![f32::INFINITY; w as usize];
+/// Raycast + muro + cielo/suelo de las columnas `[strip.x0, strip.x1)`.
+/// `zbuf`, `cover` y `covered` son los trozos de la franja (ver `WorldBuffers`).
+fn render_columns(strip: &StripView, zbuf: &mut [f32], cover: &mut [f32], covered: &mut [bool], ctx: &ColumnCtx) {
+    let ColumnCtx {
+        maze, player, block_size, tex, theme, time_s, decals, beam, fog, sky_fog,
+        row_dist, row_fog, w, y_off, h, dist_to_plane, sky_pano, horizon,
+    } = *ctx;
+    let hh = h as f32 * 0.5;
+    let bs = block_size as f32;
+    let mut layers: Vec<Intersect> = Vec::with_capacity(MAX_SEE_THROUGH);
 
-    for i in 0..w {
+    for i in strip.x0..strip.x1 {
         let t = i as f32 / w as f32;
         let ray_a = player.a - (player.fov * 0.5) + (player.fov * t);
-        let inter = cast_ray(fb, maze, player, block_size, ray_a, false);
+        let inter = cast_ray_layers(maze, player.pos, block_size, ray_a, &mut layers);
         let delta = ray_a - player.a;
         let dist = (inter.distance * delta.cos()).max(1e-4);
-        zbuf[i as usize] = dist;
+        zbuf[(i - strip.x0) as usize] = dist;
 
         let wall_real = block_size as f32;
         let line_h = ((wall_real * dist_to_plane) / dist).max(1.0);
@@ -116,53 +245,143 @@ pub fn render_world_textured(
         if draw_end   > y_max { draw_end   = y_max; }
 
         let ch = if inter.impact == ' ' { '#' } else { inter.impact };
-        let (tw, th, tdata) = tex.tex_view(ch);
+        // Muros animados: frame según el tiempo y los fps de la hoja (1 frame si no hay)
+        let frame = tex.anim_frame(ch, time_s);
+        let (_, _, fx0, fy0, tw, th, _) = tex.sheet_frame_view(ch, frame);
+        // Nivel de mip según cuántos texeles caen en cada píxel de la columna
+        let mip = tex.mip_sampler(ch, th as f32 / line_h);
 
         let mut tx = (inter.hit_frac * tw as f32).floor() as i32;
         if tx < 0 { tx = 0; }
         if tx >= tw as i32 { tx = tw as i32 - 1; }
 
+        // Decals de esta cara que cruzan esta columna: (decal, u local, sampler, w, h)
+        let mut col_decals = Vec::new();
+        if !decals.is_empty() {
+            let (face, on_face) = decals.on_face(inter.cell, inter.normal);
+            let u = if face.is_some_and(|f| f.flips_u()) { 1.0 - inter.hit_frac } else { inter.hit_frac };
+            for d in on_face {
+                let du = (u - d.u0) / d.w;
+                if !(0.0..1.0).contains(&du) { continue; }
+                let (dw, dh) = tex.tex_size(d.tex);
+                let sampler = tex.mip_sampler(d.tex, dh as f32 / (line_h * d.h).max(1.0));
+                col_decals.push((d, du, sampler, dw as f32, dh as f32));
+            }
+        }
+
         let step = th as f32 / line_h;
         let start_tex_pos = ((draw_start as f32 - ((y_off as f32) + hh - line_h * 0.5)) * step).max(0.0);
         let mut tex_pos = start_tex_pos;
 
         let shade = (1.0 / (1.0 + dist * theme.lighting.atten)).clamp(theme.lighting.shade_min, 1.0);
+        let wall_fog = fog.map_or(0.0, |f| f.factor(dist / bs));
+
+        // Cielo/techo y suelo de esta columna, iluminados por la linterna
+        let (sky, floor) = (theme.sky, theme.floor);
+        if let Some(pano) = sky_pano {
+            // El panorama no lo ilumina la linterna: está "en el infinito"
+            let (sw, sh) = tex.tex_size(SKY_GLYPH);
+            let u = ((ray_a.rem_euclid(std::f32::consts::TAU) / std::f32::consts::TAU) * sw as f32) as usize;
+            let u = u.min(sw as usize - 1);
+            let top = horizon - hh;
+            for y in y_min..draw_start {
+                let row = (y - y_off) as usize;
+                let v = (((row as f32 - top) / hh) * sh as f32).clamp(0.0, sh as f32 - 1.0) as usize;
+                let (r, g, b, _) = pano.texel(u, v);
+                let (r, g, b) = lit(r, g, b, 1.0, sky_fog, row_fog[row]);
+                strip.put_pixel_rgba(i, y, r, g, b, 255);
+            }
+        } else {
+            for y in y_min..draw_start {
+                let row = (y - y_off) as usize;
+                let light = beam.light_at(i, y, row_dist[row]);
+                let (r, g, b) = lit(sky.r, sky.g, sky.b, light, sky_fog, row_fog[row]);
+                strip.put_pixel_rgba(i, y, r, g, b, 255);
+            }
+        }
+        for y in (draw_end + 1)..=y_max {
+            let row = (y - y_off) as usize;
+            let light = beam.light_at(i, y, row_dist[row]);
+            let (r, g, b) = lit(floor.r, floor.g, floor.b, light, fog, row_fog[row]);
+            strip.put_pixel_rgba(i, y, r, g, b, 255);
+        }
 
         for y in draw_start..=draw_end {
             let mut ty = tex_pos as i32;
             if ty < 0 { ty = 0; }
             if ty >= th as i32 { ty = th as i32 - 1; }
+            let v = tex_pos / th as f32;
             tex_pos += step;
 
-            let idx = ((ty as usize * tw) + tx as usize) * 4;
-            let (r, g, b) = (tdata[idx], tdata[idx + 1], tdata[idx + 2]);
+            let (mut r, mut g, mut b, _) = mip.texel(fx0 + tx as usize, fy0 + ty as usize);
+
+            // Decals encima de la textura del muro (antes de sombrear)
+            for (d, du, sampler, dw, dh) in &col_decals {
+                let dv = (v - d.v0) / d.h;
+                if !(0.0..1.0).contains(&dv) { continue; }
+                let (dr, dg, db, da) = sampler.texel((du * dw) as usize, (dv * dh) as usize);
+                if da == 0 { continue; }
+                let a = da as f32 / 255.0;
+                let mix = |w: u8, c: u8| (w as f32 + (c as f32 - w as f32) * a) as u8;
+                (r, g, b) = (mix(r, dr), mix(g, dg), mix(b, db));
+            }
+
+            let (r, g, b) = ((r as f32 * shade) as u8, (g as f32 * shade) as u8, (b as f32 * shade) as u8);
+            let (rr, gg, bb) = lit(r, g, b, beam.light_at(i, y, dist), fog, wall_fog);
 
-            let rr = (r as f32 * shade) as u8;
-            let gg = (g as f32 * shade) as u8;
-            let bb = (b as f32 * shade) as u8;
+            strip.put_pixel_rgba(i, y, rr, gg, bb, 255);
+        }
 
-            fb.put_pixel_rgba(i, y, rr, gg, bb, 255);
+        // Rejas/alambradas delante del muro: de atrás hacia delante, con alpha.
+        // Solo las columnas con alguna limpian y usan su trozo de `cover`.
+        let c = (i - strip.x0) as usize;
+        covered[c] = !layers.is_empty();
+        if !covered[c] { continue; }
+        let cover_col = &mut cover[c * h as usize..(c + 1) * h as usize];
+        cover_col.fill(f32::INFINITY);
+        for layer in layers.iter().rev() {
+            draw_see_through(strip, cover_col, i, layer, delta, ctx);
         }
     }
+}
 
-    // Sprites decorativos estáticos
-    render_sprites(fb, player, sprites, tex, &zbuf, block_size, time_s, y_off, h, &theme.lighting);
+/// Dibuja la cara de un bloque transparente en la columna `i` mezclando con lo de
+/// detrás, y marca en `cover_col` los píxeles (casi) opacos que tapa.
+fn draw_see_through(strip: &StripView, cover_col: &mut [f32], i: i32, hit: &Intersect, delta: f32, ctx: &ColumnCtx) {
+    let ColumnCtx { block_size, tex, theme, time_s, beam, fog, y_off, h, dist_to_plane, .. } = *ctx;
+    let hh = h as f32 * 0.5;
 
-    // Llaves animadas, ocluidas por muros
-    render_sprites(fb, player, keys_sprites, tex, &zbuf, block_size, time_s, y_off, h, &theme.lighting);
+    let dist = (hit.distance * delta.cos()).max(1e-4);
+    let line_h = ((block_size as f32 * dist_to_plane) / dist).max(1.0);
+    let top = (y_off as f32) + hh - line_h * 0.5;
+    let draw_start = (top.floor() as i32).max(y_off);
+    let draw_end   = ((hh + line_h * 0.5).ceil() as i32 + y_off).min(y_off + h - 1);
 
-    // Enemigos como sprites animados dinámicos
-    use crate::sprites::Sprite as DynSprite;
-    let mut dyn_sprites: Vec<DynSprite> = Vec::new();
-    for e in enemies {
-        dyn_sprites.push(DynSprite {
-            pos: e.pos,
-            tex: 'e',
-            scale: 1.0,
-            frames: tex.sheet_frames('e'),
-            fps: 8.0,
-            phase: 0,
-        });
+    let frame = tex.anim_frame(hit.impact, time_s);
+    let (_, _, fx0, fy0, tw, th, _) = tex.sheet_frame_view(hit.impact, frame);
+    let mip = tex.mip_sampler(hit.impact, th as f32 / line_h);
+    let tx = ((hit.hit_frac * tw as f32) as usize).min(tw - 1);
+
+    let step = th as f32 / line_h;
+    let mut tex_pos = ((draw_start as f32 - top) * step).max(0.0);
+
+    let shade = (1.0 / (1.0 + dist * theme.lighting.atten)).clamp(theme.lighting.shade_min, 1.0);
+    let f = fog.map_or(0.0, |fg| fg.factor(dist / block_size as f32));
+
+    for y in draw_start..=draw_end {
+        let ty = (tex_pos as usize).min(th - 1);
+        tex_pos += step;
+
+        let (r, g, b, a) = mip.texel(fx0 + tx, fy0 + ty);
+        if a == 0 { continue; }
+
+        let (r, g, b) = ((r as f32 * shade) as u8, (g as f32 * shade) as u8, (b as f32 * shade) as u8);
+        let (rr, gg, bb) = lit(r, g, b, beam.light_at(i, y, dist), fog, f);
+        strip.blend_pixel(i, y, rr, gg, bb, a);
+
+        if a >= 128 {
+            let row = (y - y_off) as usize;
+            cover_col[row] = cover_col[row].min(dist);
+        }
     }
-    render_sprites(fb, player, &dyn_sprites, tex, &zbuf, block_size, time_s, y_off, h, &theme.lighting);
 }